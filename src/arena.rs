@@ -0,0 +1,451 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use chess::{
+    Book, Engine, FigureColor, FigureVariant, Game, GameState, Move, MAX_LEVEL, MAX_LEVEL_DEPTH,
+};
+use rand::seq::SliceRandom;
+
+const USAGE: &str = "usage: match PLAYER PLAYER [--games N] [--openings FILE] [--book FILE] \
+[--book-depth N] [--movetime MS] [--max-moves N], a player is random, minimax:DEPTH, level:1-20, \
+elo:RATING or uci:PATH";
+
+/// an engine running in another process, spoken to over the Universal Chess Interface
+struct Uci {
+    child: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+    movetime: u64,
+}
+
+impl Uci {
+    fn start(path: &str, movetime: u64) -> Result<Self, String> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("unable to start '{}': {}", path, e))?;
+        let input = child.stdin.take().ok_or("unable to talk to the engine")?;
+        let output = BufReader::new(child.stdout.take().ok_or("unable to talk to the engine")?);
+
+        let mut uci = Uci {
+            child,
+            input,
+            output,
+            movetime,
+        };
+        uci.send("uci")?;
+        uci.wait_for("uciok")?;
+        Ok(uci)
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.input, "{}", line).or(Err("the engine stopped".to_string()))
+    }
+
+    /// skips the engine's output up to the line starting with `token`
+    fn wait_for(&mut self, token: &str) -> Result<String, String> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match self.output.read_line(&mut line) {
+                Ok(0) | Err(_) => return Err("the engine stopped answering".to_string()),
+                Ok(_) if line.split_whitespace().next() == Some(token) => return Ok(line),
+                Ok(_) => (),
+            }
+        }
+    }
+
+    fn new_game(&mut self, game: &Game) -> Result<(), String> {
+        if game.is_chess960() {
+            self.send("setoption name UCI_Chess960 value true")?;
+        }
+        self.send("ucinewgame")?;
+        self.send("isready")?;
+        self.wait_for("readyok")?;
+        Ok(())
+    }
+
+    fn best_move(&mut self, game: &Game) -> Result<Move, String> {
+        let mut position = format!("position fen {}", game.start_fen());
+        if !game.history().is_empty() {
+            let moves: Vec<String> = game.history().iter().map(|m| m.to_string()).collect();
+            position.push_str(" moves ");
+            position.push_str(&moves.join(" "));
+        }
+        self.send(&position)?;
+        self.send(&format!("go movetime {}", self.movetime))?;
+
+        let line = self.wait_for("bestmove")?;
+        let answer = line.split_whitespace().nth(1).unwrap_or_default();
+        answer
+            .parse()
+            .map_err(|e| format!("the engine answered '{}': {}", answer, e))
+    }
+}
+
+impl Drop for Uci {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.child.wait();
+    }
+}
+
+/// the built-in engine of `minimax:DEPTH`, `level:1-20` or `elo:RATING`
+pub fn engine(spec: &str) -> Result<Engine, String> {
+    let number = |value: &str| {
+        value
+            .parse()
+            .map_err(|_| format!("unable to parse the number of '{}'", spec))
+    };
+    match spec.split_once(':') {
+        Some(("minimax", value)) => Ok(Engine::new(number(value)?)),
+        Some(("level", value)) => {
+            let level = value
+                .parse()
+                .ok()
+                .filter(|level| (1..=MAX_LEVEL).contains(level))
+                .ok_or(format!(
+                    "the level of '{}' goes from 1 to {}",
+                    spec, MAX_LEVEL
+                ))?;
+            Ok(Engine::new(MAX_LEVEL_DEPTH).with_level(level))
+        }
+        Some(("elo", value)) => Ok(Engine::new(MAX_LEVEL_DEPTH).with_elo(number(value)?)),
+        _ => Err(format!(
+            "unknown engine '{}', it is minimax:DEPTH, level:1-{} or elo:RATING",
+            spec, MAX_LEVEL
+        )),
+    }
+}
+
+enum Kind {
+    Random(Option<Book>),
+    Minimax(Engine),
+    Uci(Uci),
+}
+
+struct Player {
+    name: String,
+    kind: Kind,
+}
+
+impl Player {
+    /// `random`, `minimax:DEPTH`, `level:1-20`, `elo:RATING` or `uci:PATH`, all but the last
+    /// play from `book` while they can
+    fn parse(spec: &str, book: Option<&Book>, movetime: u64) -> Result<Self, String> {
+        let kind = match spec.split_once(':') {
+            None if spec == "random" => Kind::Random(book.cloned()),
+            Some(("minimax" | "level" | "elo", _)) => {
+                let engine = engine(spec)?;
+                Kind::Minimax(match book {
+                    Some(book) => engine.with_book(book.clone()),
+                    None => engine,
+                })
+            }
+            Some(("uci", path)) => Kind::Uci(Uci::start(path, movetime)?),
+            _ => return Err(format!("unknown player '{}', {}", spec, USAGE)),
+        };
+
+        Ok(Player {
+            name: spec.to_string(),
+            kind,
+        })
+    }
+
+    fn new_game(&mut self, game: &Game) -> Result<(), String> {
+        match &mut self.kind {
+            Kind::Uci(uci) => uci.new_game(game),
+            _ => Ok(()),
+        }
+    }
+
+    fn choose(&mut self, game: &Game) -> Result<Move, String> {
+        let chosen = match &mut self.kind {
+            Kind::Random(book) => book
+                .as_ref()
+                .and_then(|book| book.pick(game))
+                .or_else(|| game.legal_moves().choose(&mut rand::thread_rng()).copied()),
+            Kind::Minimax(engine) => engine.best_move(game),
+            Kind::Uci(uci) => Some(uci.best_move(game)?),
+        };
+        chosen.ok_or(format!("{} found no move", self.name))
+    }
+}
+
+enum Outcome {
+    Win(FigureColor, &'static str),
+    Draw(&'static str),
+}
+
+/// whether only kings are left, or kings and a single bishop or knight
+fn insufficient_material(game: &Game) -> bool {
+    let others: Vec<FigureVariant> = game
+        .board
+        .values()
+        .map(|f| f.variant)
+        .filter(|v| *v != FigureVariant::King)
+        .collect();
+    match others.as_slice() {
+        [] => true,
+        [only] => matches!(only, FigureVariant::Bishop | FigureVariant::Knight),
+        _ => false,
+    }
+}
+
+/// ends the game on mate or stalemate, and calls the draws that [`Game`] leaves to the players
+fn adjudicate(game: &Game, seen: &mut HashMap<u64, u32>, max_plies: usize) -> Option<Outcome> {
+    match game.state() {
+        GameState::Checkmate(color) => return Some(Outcome::Win(color.opposite(), "checkmate")),
+        GameState::Stalemate(_) => return Some(Outcome::Draw("stalemate")),
+        _ => (),
+    }
+
+    let repeated = seen.entry(game.zobrist_hash()).or_insert(0);
+    *repeated += 1;
+
+    if *repeated >= 3 {
+        Some(Outcome::Draw("threefold repetition"))
+    } else if game.halfmove_clock() >= 100 {
+        Some(Outcome::Draw("fifty-move rule"))
+    } else if insufficient_material(game) {
+        Some(Outcome::Draw("insufficient material"))
+    } else if game.history().len() >= max_plies {
+        Some(Outcome::Draw("move limit"))
+    } else {
+        None
+    }
+}
+
+fn play_game(
+    white: &mut Player,
+    black: &mut Player,
+    start: &Game,
+    max_plies: usize,
+) -> Result<Outcome, String> {
+    let mut game = start.clone();
+    white.new_game(&game)?;
+    black.new_game(&game)?;
+    let mut seen = HashMap::new();
+
+    loop {
+        if let Some(outcome) = adjudicate(&game, &mut seen, max_plies) {
+            return Ok(outcome);
+        }
+
+        let side = game.side_to_move();
+        let player = match side {
+            FigureColor::White => &mut *white,
+            FigureColor::Black => &mut *black,
+        };
+        let m = player.choose(&game)?;
+        if let Err(e) = game.apply_move(m) {
+            println!("{} played {}: {}", player.name, m, e);
+            return Ok(Outcome::Win(side.opposite(), "illegal move"));
+        }
+    }
+}
+
+/// wins, draws and losses of the first player
+#[derive(Debug, Default)]
+struct Score {
+    wins: u32,
+    draws: u32,
+    losses: u32,
+}
+
+impl Score {
+    fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// share of the points, 1 for winning every game
+    fn ratio(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games().max(1) as f64
+    }
+
+    /// the Elo difference the score suggests with the margin of its 95% confidence interval,
+    /// `None` when one player got all the points, which says nothing about how far apart they are
+    fn elo(&self) -> Option<(f64, f64)> {
+        let elo = |ratio: f64| 400.0 * (ratio / (1.0 - ratio)).log10();
+        let games = self.games() as f64;
+        let ratio = self.ratio();
+        if ratio <= 0.0 || ratio >= 1.0 {
+            return None;
+        }
+
+        let variance = (self.wins as f64 * (1.0 - ratio).powi(2)
+            + self.draws as f64 * (0.5 - ratio).powi(2)
+            + self.losses as f64 * ratio.powi(2))
+            / games;
+        let margin = 1.96 * (variance / games).sqrt();
+
+        // a margin reaching past 0 or 1 would give an infinite difference
+        let low = elo((ratio - margin).max(1e-3));
+        let high = elo((ratio + margin).min(1.0 - 1e-3));
+        Some((elo(ratio), (high - low) / 2.0))
+    }
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "+{} ={} -{}, {:.1}%, ",
+            self.wins,
+            self.draws,
+            self.losses,
+            self.ratio() * 100.0
+        )?;
+        match self.elo() {
+            Some((elo, margin)) => write!(f, "Elo difference {:+.0} ± {:.0}", elo, margin),
+            None => write!(f, "Elo difference unknown, play more games"),
+        }
+    }
+}
+
+fn read_openings(path: &str) -> Result<Vec<Game>, String> {
+    let text = fs::read_to_string(path).map_err(|_| format!("unable to read '{}'", path))?;
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|fen| {
+            let game = Game::from_fen(fen).map_err(|e| format!("{}: {}", fen, e))?;
+            game.validate()
+                .map_err(|_| format!("{}: the position cannot be played", fen))?;
+            Ok(game)
+        })
+        .collect()
+}
+
+/// plays a match between two players and prints every result and the final score
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut specs = Vec::new();
+    let mut games = 10;
+    let mut openings = vec![Game::new()];
+    let mut book = None;
+    let mut book_depth = None;
+    let mut movetime = 100;
+    let mut max_moves = 200;
+
+    let number = |value: Option<String>, flag: &str| {
+        value
+            .and_then(|v| v.parse::<u32>().ok())
+            .ok_or(format!("{} needs a number", flag))
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => games = number(args.next(), "--games")?,
+            "--movetime" => movetime = number(args.next(), "--movetime")? as u64,
+            "--max-moves" => max_moves = number(args.next(), "--max-moves")?,
+            "--book-depth" => book_depth = Some(number(args.next(), "--book-depth")?),
+            "--book" => book = Some(Book::open(&args.next().ok_or("--book needs a file")?)?),
+            "--openings" => {
+                openings = read_openings(&args.next().ok_or("--openings needs a file")?)?;
+                if openings.is_empty() {
+                    return Err("the openings file has no positions".to_string());
+                }
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown argument '{}'", arg)),
+            _ => specs.push(arg),
+        }
+    }
+
+    let [first, second] = specs.as_slice() else {
+        return Err(USAGE.to_string());
+    };
+    if let Some(depth) = book_depth {
+        book = book.map(|book| book.with_max_depth(depth));
+    }
+    let mut players = [
+        Player::parse(first, book.as_ref(), movetime)?,
+        Player::parse(second, book.as_ref(), movetime)?,
+    ];
+
+    let mut score = Score::default();
+    for round in 0..games {
+        // every opening is played twice, with the colors swapped the second time
+        let start = &openings[(round / 2) as usize % openings.len()];
+        let first_is_white = round % 2 == 0;
+        let [a, b] = &mut players;
+        let (white, black) = if first_is_white { (a, b) } else { (b, a) };
+
+        let outcome = play_game(white, black, start, max_moves as usize * 2)?;
+        let (result, reason, first_won) = match outcome {
+            Outcome::Win(FigureColor::White, reason) => ("1-0", reason, Some(first_is_white)),
+            Outcome::Win(FigureColor::Black, reason) => ("0-1", reason, Some(!first_is_white)),
+            Outcome::Draw(reason) => ("1/2-1/2", reason, None),
+        };
+        match first_won {
+            Some(true) => score.wins += 1,
+            Some(false) => score.losses += 1,
+            None => score.draws += 1,
+        }
+
+        println!(
+            "game {}/{}: {} - {} {} ({})",
+            round + 1,
+            games,
+            white.name,
+            black.name,
+            result,
+            reason
+        );
+    }
+
+    println!("{} vs {}: {}", first, second, score);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(wins: u32, draws: u32, losses: u32) -> Score {
+        Score {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    #[test]
+    fn elo() {
+        let (elo, margin) = score(3, 0, 1).elo().unwrap();
+        assert!((elo - 190.8).abs() < 0.1);
+        assert!(margin.is_finite() && margin > 0.0);
+
+        let (elo, margin) = score(1, 0, 3).elo().unwrap();
+        assert!((elo + 190.8).abs() < 0.1);
+        assert!(margin.is_finite());
+
+        assert_eq!(score(0, 10, 0).elo().unwrap().0, 0.0);
+        assert_eq!(score(4, 0, 0).elo(), None);
+        assert_eq!(score(0, 0, 4).elo(), None);
+        assert_eq!(score(0, 0, 0).elo(), None);
+    }
+
+    #[test]
+    fn margin_stays_finite_for_few_games() {
+        let (elo, margin) = score(1, 0, 1).elo().unwrap();
+        assert_eq!(elo, 0.0);
+        assert!((margin - 1199.9).abs() < 0.1);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            score(1, 0, 1).to_string(),
+            "+1 =0 -1, 50.0%, Elo difference +0 ± 1200"
+        );
+        assert_eq!(
+            score(3, 0, 0).to_string(),
+            "+3 =0 -0, 100.0%, Elo difference unknown, play more games"
+        );
+    }
+}
@@ -0,0 +1,448 @@
+use std::{
+    collections::HashMap,
+    env,
+    io::Read,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use chess::{Engine, FigureColor, Game, GameState, Move};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tiny_http::{Header, Method, Response, Server};
+
+const PORT: u16 = 8080;
+const USAGE: &str = "usage: chess-http [--port N]";
+/// how many requests are answered at once, each engine move keeps one of them busy
+const WORKERS: usize = 4;
+/// deeper searches would keep a worker busy for too long
+const MAX_DEPTH: u32 = 5;
+/// longer than any position can be written in
+const MAX_FEN_LEN: usize = 100;
+/// more than any request needs, longer bodies are refused unread
+const MAX_BODY: u64 = 4096;
+/// games nobody asked about for this long are deleted
+const IDLE: Duration = Duration::from_secs(30 * 60);
+
+/// what `GET /games/{id}` and every other request on a game returns
+#[derive(Debug, Serialize)]
+struct GameView {
+    id: u32,
+    fen: String,
+    side_to_move: FigureColor,
+    state: GameState,
+    legal_moves: Vec<Move>,
+    history: Vec<Move>,
+    san: Vec<String>,
+}
+
+impl GameView {
+    fn new(id: u32, game: &Game) -> Self {
+        GameView {
+            id,
+            fen: game.to_fen(),
+            side_to_move: game.side_to_move(),
+            state: game.state(),
+            legal_moves: game.legal_moves(),
+            history: game.history().to_vec(),
+            san: game.san_history(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct NewGame {
+    fen: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlayMove {
+    /// `e2e4` or SAN
+    #[serde(rename = "move")]
+    m: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct EngineMove {
+    depth: Option<u32>,
+    /// plays weaker, from 1 to 20, instead of searching to `depth`
+    level: Option<u8>,
+}
+
+/// a status code and the JSON to answer with
+type Reply = (u16, serde_json::Value);
+
+fn error(status: u16, message: &str) -> Reply {
+    (status, json!({ "error": message }))
+}
+
+/// reads the JSON body, an empty body is the same as `{}`
+fn body<T: for<'de> Deserialize<'de> + Default>(body: &str) -> Result<T, Reply> {
+    if body.trim().is_empty() {
+        return Ok(T::default());
+    }
+    serde_json::from_str(body).map_err(|e| error(400, &format!("unable to read the body: {}", e)))
+}
+
+/// letters, digits, slashes, dashes and spaces are all a FEN is made of
+fn fen_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "/- ".contains(c)
+}
+
+/// a game and when a request last used it
+struct Stored {
+    game: Game,
+    used: Instant,
+}
+
+/// what a request comes to, the engine searches a copy of the game without holding the others
+enum Routed {
+    Reply(Reply),
+    Search { id: u32, game: Game, engine: Engine },
+}
+
+#[derive(Default)]
+struct Games {
+    next_id: u32,
+    games: HashMap<u32, Stored>,
+}
+
+impl Games {
+    /// the game, which counts as used now
+    fn get(&mut self, id: u32) -> Option<&mut Game> {
+        let stored = self.games.get_mut(&id)?;
+        stored.used = Instant::now();
+        Some(&mut stored.game)
+    }
+
+    /// deletes the games idle for longer than `IDLE` at `now`
+    fn expire(&mut self, now: Instant) {
+        self.games
+            .retain(|_, stored| now.duration_since(stored.used) <= IDLE);
+    }
+
+    fn create(&mut self, request: NewGame) -> Reply {
+        let game = match request.fen {
+            Some(fen) if fen.len() > MAX_FEN_LEN => return error(400, "the FEN is too long"),
+            Some(fen) if !fen.chars().all(fen_char) => {
+                return error(400, "the FEN contains characters no position has")
+            }
+            Some(fen) => match Game::from_fen(&fen) {
+                Ok(game) if game.validate().is_ok() => game,
+                Ok(_) => return error(400, "the position cannot be played"),
+                Err(e) => return error(400, e),
+            },
+            None => Game::new(),
+        };
+
+        self.next_id += 1;
+        let view = GameView::new(self.next_id, &game);
+        let used = Instant::now();
+        self.games.insert(self.next_id, Stored { game, used });
+        (201, json!(view))
+    }
+
+    fn play(&mut self, id: u32, request: PlayMove) -> Reply {
+        let Some(game) = self.get(id) else {
+            return error(404, "there is no such game");
+        };
+        let input = request.m.trim();
+        let played = input
+            .parse()
+            .or_else(|e| game.parse_san(input).or(Err(e)))
+            .and_then(|m| game.apply_move(m));
+        match played {
+            Ok(()) => (200, json!(GameView::new(id, game))),
+            Err(e) => error(400, e),
+        }
+    }
+
+    /// the engine and the position it has to search for its move
+    fn engine(&mut self, id: u32, request: EngineMove) -> Routed {
+        let Some(game) = self.get(id) else {
+            return Routed::Reply(error(404, "there is no such game"));
+        };
+        let depth = request.depth.unwrap_or(3).clamp(1, MAX_DEPTH);
+        let engine = match request.level {
+            Some(level) => Engine::new(depth).with_level(level),
+            None => Engine::new(depth),
+        };
+        Routed::Search {
+            id,
+            game: game.clone(),
+            engine,
+        }
+    }
+
+    /// plays the move the engine found in `searched` and returns it with its score, in
+    /// centipawns for the side that moved
+    fn engine_moved(&mut self, id: u32, searched: &Game, m: Move, score: i32) -> Reply {
+        let Some(game) = self.get(id) else {
+            return error(404, "there is no such game");
+        };
+        if game.history() != searched.history() {
+            return error(409, "the game changed while the engine was searching");
+        }
+
+        let san = game.san(&m);
+        if let Err(e) = game.apply_move(m) {
+            return error(500, e);
+        }
+        let reply = json!({
+            "move": m,
+            "san": san,
+            "score": score,
+            "game": GameView::new(id, game),
+        });
+        (200, reply)
+    }
+
+    fn route(&mut self, method: &Method, url: &str, body_text: &str) -> Routed {
+        self.expire(Instant::now());
+        let path = url.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let id = segments.get(1).map(|id| id.parse::<u32>());
+
+        let result = match (method, segments.as_slice(), id) {
+            (Method::Post, ["games"], _) => body(body_text).map(|request| self.create(request)),
+            (_, ["games", ..], Some(Err(_))) => Err(error(404, "game ids are numbers")),
+            (Method::Get, ["games", _], Some(Ok(id))) => Ok(match self.get(id) {
+                Some(game) => (200, json!(GameView::new(id, game))),
+                None => error(404, "there is no such game"),
+            }),
+            (Method::Delete, ["games", _], Some(Ok(id))) => Ok(match self.games.remove(&id) {
+                Some(_) => (200, json!({ "deleted": id })),
+                None => error(404, "there is no such game"),
+            }),
+            (Method::Post, ["games", _, "moves"], Some(Ok(id))) => serde_json::from_str(body_text)
+                .map_err(|e| error(400, &format!("unable to read the body: {}", e)))
+                .map(|request| self.play(id, request)),
+            (Method::Post, ["games", _, "engine"], Some(Ok(id))) => {
+                return match body(body_text) {
+                    Ok(request) => self.engine(id, request),
+                    Err(reply) => Routed::Reply(reply),
+                };
+            }
+            (_, ["games"] | ["games", _] | ["games", _, "moves" | "engine"], _) => {
+                Err(error(405, "the method is not allowed here"))
+            }
+            _ => Err(error(404, "unknown path")),
+        };
+        Routed::Reply(result.unwrap_or_else(|reply| reply))
+    }
+}
+
+/// answers a request, other requests go on while the engine searches
+fn handle(games: &Mutex<Games>, method: &Method, url: &str, body: &str) -> Reply {
+    let routed = games.lock().unwrap().route(method, url, body);
+    match routed {
+        Routed::Reply(reply) => reply,
+        Routed::Search { id, game, engine } => match engine.search(&game) {
+            Some((m, score)) => games.lock().unwrap().engine_moved(id, &game, m, score),
+            None => error(400, "the game is already over"),
+        },
+    }
+}
+
+/// answers requests until the server stops
+fn work(server: &Server, games: &Mutex<Games>, content_type: &Header) {
+    while let Ok(mut request) = server.recv() {
+        let mut text = String::new();
+        let read = request
+            .as_reader()
+            .take(MAX_BODY + 1)
+            .read_to_string(&mut text);
+        let (status, reply) = match read {
+            Err(_) => error(400, "the body has to be UTF-8"),
+            Ok(_) if text.len() as u64 > MAX_BODY => error(413, "the body is too long"),
+            Ok(_) => handle(games, request.method(), request.url(), &text),
+        };
+
+        let response = Response::from_string(reply.to_string())
+            .with_status_code(status)
+            .with_header(content_type.clone());
+        let _ = request.respond(response);
+    }
+}
+
+fn main() -> Result<(), String> {
+    let mut args = env::args().skip(1);
+    let mut port = PORT;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => {
+                port = args
+                    .next()
+                    .and_then(|p| p.parse().ok())
+                    .ok_or("--port needs a number")?
+            }
+            _ => return Err(format!("unknown argument '{}', {}", arg, USAGE)),
+        }
+    }
+
+    let server = Server::http(("0.0.0.0", port))
+        .map_err(|e| format!("unable to listen on port {}: {}", port, e))?;
+    println!("listening on http://localhost:{}", port);
+    let content_type =
+        Header::from_bytes("Content-Type", "application/json").expect("the header is valid ASCII");
+
+    let server = Arc::new(server);
+    let games = Arc::new(Mutex::new(Games::default()));
+    let workers: Vec<_> = (0..WORKERS)
+        .map(|_| {
+            let (server, games, content_type) = (
+                Arc::clone(&server),
+                Arc::clone(&games),
+                content_type.clone(),
+            );
+            thread::spawn(move || work(&server, &games, &content_type))
+        })
+        .collect();
+    for worker in workers {
+        let _ = worker.join();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(games: &Mutex<Games>, url: &str, body: &str) -> Reply {
+        handle(games, &Method::Post, url, body)
+    }
+
+    #[test]
+    fn creates_and_plays_games() {
+        let games = Mutex::new(Games::default());
+        let (status, view) = post(&games, "/games", "");
+        assert_eq!(status, 201);
+        assert_eq!(view["id"], 1);
+        assert_eq!(view["legal_moves"].as_array().map(Vec::len), Some(20));
+
+        let (status, view) = post(&games, "/games/1/moves", r#"{"move": "e2e4"}"#);
+        assert_eq!(status, 200);
+        let (status, view2) = post(&games, "/games/1/moves", r#"{"move": "Nf6"}"#);
+        assert_eq!(status, 200);
+        assert_eq!(view["san"], json!(["e4"]));
+        assert_eq!(view2["san"], json!(["e4", "Nf6"]));
+
+        let (status, view) = handle(&games, &Method::Get, "/games/1", "");
+        assert_eq!((status, &view["side_to_move"]), (200, &json!("White")));
+        let (status, _) = handle(&games, &Method::Delete, "/games/1", "");
+        assert_eq!(status, 200);
+        let (status, _) = handle(&games, &Method::Get, "/games/1", "");
+        assert_eq!(status, 404);
+    }
+
+    #[test]
+    fn creates_games_from_fen() {
+        let games = Mutex::new(Games::default());
+        let fen = "4k3/8/8/8/8/8/8/4K2R w K - 0 1";
+        let (status, view) = post(&games, "/games", &json!({ "fen": fen }).to_string());
+        assert_eq!(status, 201);
+        assert_eq!(view["fen"], fen);
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        let games = Mutex::new(Games::default());
+        for fen in [
+            "not a fen",
+            "8/8/8/8/8/8/8/8 w - - 0 1",
+            "99999999999999999999999999999/8/8/8/8/8/8/8 w - -",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 4294967295",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1 \u{0}",
+            &"8/".repeat(100),
+        ] {
+            let body = json!({ "fen": fen }).to_string();
+            assert_eq!(post(&games, "/games", &body).0, 400, "{}", fen);
+        }
+        assert_eq!(post(&games, "/games", "{").0, 400);
+        assert!(games.lock().unwrap().games.is_empty());
+
+        post(&games, "/games", "");
+        for body in ["", "{}", r#"{"move": "e2e5"}"#, r#"{"move": "Qh5"}"#] {
+            assert_eq!(post(&games, "/games/1/moves", body).0, 400, "{}", body);
+        }
+    }
+
+    #[test]
+    fn unknown_games_and_paths() {
+        let games = Mutex::new(Games::default());
+        assert_eq!(handle(&games, &Method::Get, "/games/1", "").0, 404);
+        assert_eq!(handle(&games, &Method::Get, "/games/x", "").0, 404);
+        assert_eq!(handle(&games, &Method::Delete, "/games/7", "").0, 404);
+        assert_eq!(post(&games, "/games/3/moves", r#"{"move": "e4"}"#).0, 404);
+        assert_eq!(post(&games, "/games/3/engine", "").0, 404);
+        assert_eq!(handle(&games, &Method::Get, "/", "").0, 404);
+        assert_eq!(handle(&games, &Method::Get, "/players", "").0, 404);
+    }
+
+    #[test]
+    fn wrong_methods() {
+        let games = Mutex::new(Games::default());
+        post(&games, "/games", "");
+        assert_eq!(handle(&games, &Method::Get, "/games", "").0, 405);
+        assert_eq!(handle(&games, &Method::Put, "/games/1", "").0, 405);
+        assert_eq!(handle(&games, &Method::Get, "/games/1/moves", "").0, 405);
+        assert_eq!(
+            handle(&games, &Method::Delete, "/games/1/engine", "").0,
+            405
+        );
+    }
+
+    #[test]
+    fn engine_moves() {
+        let games = Mutex::new(Games::default());
+        let fen = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+        post(&games, "/games", &json!({ "fen": fen }).to_string());
+        let (status, reply) = post(&games, "/games/1/engine", r#"{"depth": 2}"#);
+        assert_eq!(status, 200);
+        assert_eq!(reply["san"], "Ra8#");
+        assert!(reply["game"]["state"].get("Checkmate").is_some());
+        assert_eq!(post(&games, "/games/1/engine", "").0, 400);
+
+        post(&games, "/games", "");
+        let (status, reply) = post(&games, "/games/2/engine", r#"{"level": 1}"#);
+        assert_eq!(status, 200);
+        assert_eq!(reply["game"]["history"].as_array().map(Vec::len), Some(1));
+        assert_eq!(post(&games, "/games/2/engine", r#"{"depth": "x"}"#).0, 400);
+    }
+
+    #[test]
+    fn engine_moves_only_in_the_searched_position() {
+        let games = Mutex::new(Games::default());
+        post(&games, "/games", "");
+        let Routed::Search { id, game, engine } =
+            games
+                .lock()
+                .unwrap()
+                .route(&Method::Post, "/games/1/engine", r#"{"depth": 1}"#)
+        else {
+            panic!("the engine searches");
+        };
+        let (m, score) = engine.search(&game).unwrap();
+        // a move came in meanwhile
+        post(&games, "/games/1/moves", r#"{"move": "e4"}"#);
+        let mut locked = games.lock().unwrap();
+        assert_eq!(locked.engine_moved(id, &game, m, score).0, 409);
+        assert_eq!(locked.get(1).map(|game| game.history().len()), Some(1));
+    }
+
+    #[test]
+    fn idle_games_expire() {
+        let games = Mutex::new(Games::default());
+        post(&games, "/games", "");
+        post(&games, "/games", "");
+        let later = Instant::now() + IDLE + Duration::from_secs(1);
+        let mut locked = games.lock().unwrap();
+        locked.games.get_mut(&2).unwrap().used = later;
+        locked.expire(later);
+        assert!(locked.get(1).is_none());
+        assert!(locked.get(2).is_some());
+        drop(locked);
+        assert_eq!(handle(&games, &Method::Get, "/games/1", "").0, 404);
+    }
+}
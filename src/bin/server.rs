@@ -0,0 +1,503 @@
+use std::{
+    collections::HashMap,
+    env,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use chess::{Clock, FigureColor, Game, GameState, Move};
+use serde::{Deserialize, Deserializer, Serialize};
+
+const PORT: u16 = 7879;
+const USAGE: &str = "usage: chess-server [--port N]";
+/// how many events may wait for a client before it counts as too slow and is dropped
+const OUTBOX: usize = 256;
+/// the longest request line read, a client sending more is cut off
+const MAX_LINE: u64 = 4096;
+
+/// a time control as the client wrote it, with the clock it stands for
+#[derive(Debug)]
+struct TimeControl {
+    text: String,
+    clock: Clock,
+}
+
+/// checks the time control while the request is read, before the server is locked
+fn time_control<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<TimeControl>, D::Error> {
+    let Some(text) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    let clock = Clock::from_time_control(&text).map_err(serde::de::Error::custom)?;
+    Ok(Some(TimeControl { text, clock }))
+}
+
+/// what clients send, one JSON object per line, eg `{"type": "seek", "time_control": "5+3"}`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Request {
+    Hello {
+        name: String,
+    },
+    /// waits in the lobby for someone seeking the same time control, unlimited without one
+    Seek {
+        #[serde(default, deserialize_with = "time_control")]
+        time_control: Option<TimeControl>,
+    },
+    Move {
+        game: u32,
+        #[serde(rename = "move")]
+        m: Move,
+    },
+    Resign {
+        game: u32,
+    },
+    List,
+    Watch {
+        game: u32,
+    },
+}
+
+/// what the server sends, one JSON object per line
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Event {
+    Welcome {
+        name: String,
+    },
+    Seeking {
+        time_control: Option<String>,
+    },
+    Started {
+        game: u32,
+        color: FigureColor,
+        white: String,
+        black: String,
+    },
+    /// sent to both players and the spectators after every move
+    Update {
+        game: u32,
+        position: Game,
+        white_ms: Option<u64>,
+        black_ms: Option<u64>,
+    },
+    Over {
+        game: u32,
+        result: String,
+        reason: String,
+    },
+    Games {
+        games: Vec<Summary>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct Summary {
+    game: u32,
+    white: String,
+    black: String,
+    moves: usize,
+    result: Option<String>,
+}
+
+struct Client {
+    name: String,
+    /// the lines its writer thread still has to send
+    outbox: SyncSender<String>,
+    stream: TcpStream,
+}
+
+/// a game being played on the server, with its clock and who is watching it
+struct Table {
+    players: [u32; 2],
+    names: [String; 2],
+    game: Game,
+    clock: Clock,
+    spectators: Vec<u32>,
+    result: Option<String>,
+}
+
+impl Table {
+    fn player(&self, color: FigureColor) -> u32 {
+        match color {
+            FigureColor::White => self.players[0],
+            FigureColor::Black => self.players[1],
+        }
+    }
+
+    fn color_of(&self, client: u32) -> Option<FigureColor> {
+        [FigureColor::White, FigureColor::Black]
+            .into_iter()
+            .find(|color| self.player(*color) == client)
+    }
+
+    /// everyone who gets the updates of the game
+    fn audience(&self) -> Vec<u32> {
+        self.players
+            .iter()
+            .chain(&self.spectators)
+            .copied()
+            .collect()
+    }
+
+    fn update(&self, id: u32) -> Event {
+        let ms = |color| {
+            self.clock
+                .remaining(color)
+                .map(|left: Duration| left.as_millis() as u64)
+        };
+        Event::Update {
+            game: id,
+            position: self.game.clone(),
+            white_ms: ms(FigureColor::White),
+            black_ms: ms(FigureColor::Black),
+        }
+    }
+}
+
+fn win_for(color: FigureColor) -> String {
+    match color {
+        FigureColor::White => "1-0".to_string(),
+        FigureColor::Black => "0-1".to_string(),
+    }
+}
+
+#[derive(Default)]
+struct Server {
+    next_client: u32,
+    next_game: u32,
+    clients: HashMap<u32, Client>,
+    seeks: Vec<(u32, Option<String>)>,
+    tables: HashMap<u32, Table>,
+}
+
+impl Server {
+    fn send(&mut self, client: u32, event: &Event) {
+        if let Some(client) = self.clients.get_mut(&client) {
+            let line = serde_json::to_string(event).expect("events are plain data");
+            // a client that cannot keep up is cut off, its reader then cleans it up
+            if let Err(TrySendError::Full(_)) = client.outbox.try_send(line) {
+                let _ = client.stream.shutdown(Shutdown::Both);
+            }
+        }
+    }
+
+    fn broadcast(&mut self, table: u32, event: &Event) {
+        let audience = self.tables.get(&table).map_or(Vec::new(), Table::audience);
+        for client in audience {
+            self.send(client, event);
+        }
+    }
+
+    fn connect(&mut self, outbox: SyncSender<String>, stream: TcpStream) -> u32 {
+        self.next_client += 1;
+        let id = self.next_client;
+        let name = format!("guest{}", id);
+        let client = Client {
+            name,
+            outbox,
+            stream,
+        };
+        self.clients.insert(id, client);
+        id
+    }
+
+    /// ends the game and tells everyone watching it
+    fn finish(&mut self, id: u32, result: String, reason: &str) {
+        let Some(table) = self.tables.get_mut(&id) else {
+            return;
+        };
+        table.clock.stop();
+        table.result = Some(result.clone());
+        let event = Event::Over {
+            game: id,
+            result,
+            reason: reason.to_string(),
+        };
+        self.broadcast(id, &event);
+        self.prune();
+    }
+
+    /// forgets the finished games nobody is watching anymore
+    fn prune(&mut self) {
+        self.tables
+            .retain(|_, table| table.result.is_none() || !table.spectators.is_empty());
+    }
+
+    fn disconnect(&mut self, client: u32) {
+        self.clients.remove(&client);
+        self.seeks.retain(|(seeker, _)| *seeker != client);
+
+        let mut forfeited = Vec::new();
+        for (id, table) in self.tables.iter_mut() {
+            table.spectators.retain(|s| *s != client);
+            if let (Some(color), None) = (table.color_of(client), &table.result) {
+                forfeited.push((*id, color));
+            }
+        }
+        for (id, color) in forfeited {
+            self.finish(id, win_for(color.opposite()), "disconnected");
+        }
+        self.prune();
+    }
+
+    /// ends the games whose side to move ran out of time
+    fn check_clocks(&mut self) {
+        let flagged: Vec<(u32, FigureColor)> = self
+            .tables
+            .iter()
+            .filter(|(_, table)| table.result.is_none())
+            .filter_map(|(id, table)| table.clock.flagged().map(|color| (*id, color)))
+            .collect();
+        for (id, color) in flagged {
+            self.lose_on_time(id, color);
+        }
+    }
+
+    fn lose_on_time(&mut self, id: u32, color: FigureColor) {
+        if let Some(table) = self.tables.get_mut(&id) {
+            table.game.lose_on_time(color);
+        }
+        self.finish(id, win_for(color.opposite()), "time");
+    }
+
+    fn seek(&mut self, client: u32, time_control: Option<TimeControl>) {
+        let (time_control, clock) = match time_control {
+            Some(control) => (Some(control.text), control.clock),
+            None => (None, Clock::unlimited()),
+        };
+        // seeking again replaces the earlier seek
+        self.seeks.retain(|(seeker, _)| *seeker != client);
+        let opponent = self
+            .seeks
+            .iter()
+            .position(|(_, control)| *control == time_control);
+        let Some(opponent) = opponent else {
+            self.seeks.push((client, time_control.clone()));
+            self.send(client, &Event::Seeking { time_control });
+            return;
+        };
+
+        // whoever waited longer plays white
+        let (white, _) = self.seeks.remove(opponent);
+        let name = |id| {
+            self.clients
+                .get(&id)
+                .map_or(String::new(), |c| c.name.clone())
+        };
+        let mut table = Table {
+            players: [white, client],
+            names: [name(white), name(client)],
+            game: Game::new(),
+            clock,
+            spectators: Vec::new(),
+            result: None,
+        };
+        table.clock.start(FigureColor::White);
+
+        self.next_game += 1;
+        let id = self.next_game;
+        for (player, color) in [(white, FigureColor::White), (client, FigureColor::Black)] {
+            let event = Event::Started {
+                game: id,
+                color,
+                white: table.names[0].clone(),
+                black: table.names[1].clone(),
+            };
+            self.send(player, &event);
+        }
+        let update = table.update(id);
+        self.tables.insert(id, table);
+        self.broadcast(id, &update);
+    }
+
+    fn play(&mut self, client: u32, id: u32, m: Move) -> Result<(), String> {
+        let table = self.tables.get_mut(&id).ok_or("there is no such game")?;
+        if table.result.is_some() {
+            return Err("the game is over".to_string());
+        }
+        // the clocks are only checked every so often, a move after the flag fell is too late
+        if let Some(color) = table.clock.flagged() {
+            self.lose_on_time(id, color);
+            return Err("the game is over".to_string());
+        }
+        if table.color_of(client) != Some(table.game.side_to_move()) {
+            return Err("it is not your turn".to_string());
+        }
+        table.game.apply_move(m)?;
+        table.clock.press();
+
+        let update = table.update(id);
+        let state = table.game.state();
+        self.broadcast(id, &update);
+        match state {
+            GameState::Checkmate(color) => self.finish(id, win_for(color.opposite()), "checkmate"),
+            GameState::Stalemate(_) => self.finish(id, "1/2-1/2".to_string(), "stalemate"),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, client: u32, request: Request) -> Result<(), String> {
+        match request {
+            Request::Hello { name } => {
+                if let Some(c) = self.clients.get_mut(&client) {
+                    c.name = name.clone();
+                }
+                self.send(client, &Event::Welcome { name });
+            }
+            Request::Seek { time_control } => self.seek(client, time_control),
+            Request::Move { game, m } => self.play(client, game, m)?,
+            Request::Resign { game } => {
+                let table = self.tables.get(&game).ok_or("there is no such game")?;
+                let color = table
+                    .color_of(client)
+                    .ok_or("you do not play in that game")?;
+                if table.result.is_some() {
+                    return Err("the game is over".to_string());
+                }
+                self.finish(game, win_for(color.opposite()), "resignation");
+            }
+            Request::List => {
+                let mut games: Vec<Summary> = self
+                    .tables
+                    .iter()
+                    .map(|(id, table)| Summary {
+                        game: *id,
+                        white: table.names[0].clone(),
+                        black: table.names[1].clone(),
+                        moves: table.game.history().len(),
+                        result: table.result.clone(),
+                    })
+                    .collect();
+                games.sort_by_key(|summary| summary.game);
+                self.send(client, &Event::Games { games });
+            }
+            Request::Watch { game } => {
+                let table = self.tables.get_mut(&game).ok_or("there is no such game")?;
+                if !table.audience().contains(&client) {
+                    table.spectators.push(client);
+                }
+                let update = table.update(game);
+                self.send(client, &update);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// writes the events of one client, so a slow connection never holds up the server
+fn write(mut stream: TcpStream, outbox: Receiver<String>) {
+    for line in outbox {
+        if writeln!(stream, "{}", line).is_err() {
+            let _ = stream.shutdown(Shutdown::Both);
+            return;
+        }
+    }
+}
+
+/// reads one line of at most `MAX_LINE` bytes, `None` once the client is gone
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = Vec::new();
+    reader
+        .by_ref()
+        .take(MAX_LINE + 1)
+        .read_until(b'\n', &mut line)?;
+    if line.is_empty() {
+        return Ok(None);
+    }
+    if line.len() as u64 > MAX_LINE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the request is too long",
+        ));
+    }
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// reads the requests of one client until it disconnects
+fn serve(server: Arc<Mutex<Server>>, stream: TcpStream) {
+    let (Ok(writer), Ok(closer)) = (stream.try_clone(), stream.try_clone()) else {
+        return;
+    };
+    let (outbox, lines) = mpsc::sync_channel(OUTBOX);
+    thread::spawn(move || write(writer, lines));
+    let client = server.lock().unwrap().connect(outbox, closer);
+
+    let mut reader = BufReader::new(stream);
+    loop {
+        let line = match read_line(&mut reader) {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                let message = e.to_string();
+                server
+                    .lock()
+                    .unwrap()
+                    .send(client, &Event::Error { message });
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // requests are read and checked before the lock, nothing under it fails on bad input
+        let request =
+            serde_json::from_str(&line).map_err(|e| format!("unable to read the request: {}", e));
+        let mut server = server.lock().unwrap();
+        let handled = request.and_then(|request| server.handle(client, request));
+        if let Err(message) = handled {
+            server.send(client, &Event::Error { message });
+        }
+    }
+
+    server.lock().unwrap().disconnect(client);
+}
+
+fn main() -> Result<(), String> {
+    let mut args = env::args().skip(1);
+    let mut port = PORT;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => {
+                port = args
+                    .next()
+                    .and_then(|p| p.parse().ok())
+                    .ok_or("--port needs a number")?
+            }
+            _ => return Err(format!("unknown argument '{}', {}", arg, USAGE)),
+        }
+    }
+
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .map_err(|e| format!("unable to listen on port {}: {}", port, e))?;
+    // port 0 lets the system pick one
+    let port = listener.local_addr().map_or(port, |address| address.port());
+    println!("listening on port {}", port);
+
+    let server = Arc::new(Mutex::new(Server::default()));
+    let clocks = Arc::clone(&server);
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_millis(100));
+        clocks.lock().unwrap().check_clocks();
+    });
+
+    for stream in listener.incoming().flatten() {
+        let server = Arc::clone(&server);
+        thread::spawn(move || serve(server, stream));
+    }
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use std::fs;
+
+use rand::Rng;
+
+use crate::{figure::FigureVariant, game::Game, moves::Move, square::Square};
+
+/// a position and a move of a Polyglot book, as stored in the file
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    key: u64,
+    raw_move: u16,
+    weight: u16,
+}
+
+/// an opening book in the Polyglot `.bin` format
+#[derive(Debug, Clone)]
+pub struct Book {
+    entries: Vec<Entry>,
+    max_depth: Option<u32>,
+}
+
+impl Book {
+    pub fn open(path: &str) -> Result<Self, &'static str> {
+        let bytes = fs::read(path).or(Err("unable to read the book file"))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// reads the 16 byte entries of a Polyglot book, a big endian key, move, weight and learn value each
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        if !bytes.len().is_multiple_of(16) {
+            return Err("a Polyglot book is made of 16 byte entries");
+        }
+
+        let mut entries: Vec<Entry> = bytes
+            .chunks_exact(16)
+            .map(|entry| Entry {
+                key: u64::from_be_bytes(entry[0..8].try_into().unwrap()),
+                raw_move: u16::from_be_bytes(entry[8..10].try_into().unwrap()),
+                weight: u16::from_be_bytes(entry[10..12].try_into().unwrap()),
+            })
+            .collect();
+        // books are sorted by key already, but the lookup depends on it
+        entries.sort_by_key(|e| e.key);
+
+        Ok(Book {
+            entries,
+            max_depth: None,
+        })
+    }
+
+    /// stops [`Book::pick`] from playing book moves once the game is `plies` half-moves long
+    pub fn with_max_depth(mut self, plies: u32) -> Self {
+        self.max_depth = Some(plies);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// the legal book moves of the position with their weights, heaviest first,
+    /// regardless of the maximum depth
+    pub fn moves(&self, game: &Game) -> Vec<(Move, u16)> {
+        let key = game.zobrist_hash();
+        let start = self.entries.partition_point(|e| e.key < key);
+        let legal = game.legal_moves();
+
+        let mut moves: Vec<(Move, u16)> = self.entries[start..]
+            .iter()
+            .take_while(|e| e.key == key)
+            .filter_map(|e| {
+                legal
+                    .iter()
+                    .find(|m| encode(game, m) == e.raw_move)
+                    .map(|m| (*m, e.weight))
+            })
+            .collect();
+        moves.sort_by_key(|(_, weight)| std::cmp::Reverse(*weight));
+        moves
+    }
+
+    /// a book move picked at random, each one as likely as its weight says,
+    /// `None` when the position is not in the book or the game is past the maximum depth
+    pub fn pick(&self, game: &Game) -> Option<Move> {
+        if self.max_depth.is_some_and(|depth| game.turns() >= depth) {
+            return None;
+        }
+
+        let moves = self.moves(game);
+        let total: u32 = moves.iter().map(|(_, weight)| *weight as u32).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut roll = rand::thread_rng().gen_range(0..total);
+        for (m, weight) in moves {
+            if roll < weight as u32 {
+                return Some(m);
+            }
+            roll -= weight as u32;
+        }
+        None
+    }
+}
+
+/// the move the way Polyglot stores it, castling is always the king taking its own rook
+fn encode(game: &Game, m: &Move) -> u16 {
+    let to = match game.castling_side(m) {
+        Some(kingside) => game
+            .castling_rights()
+            .get(game.side_to_move(), kingside)
+            .map_or(m.to, |file| Square::new(file, m.from.rank())),
+        None => m.to,
+    };
+    let promotion = match m.promotion {
+        Some(FigureVariant::Knight) => 1,
+        Some(FigureVariant::Bishop) => 2,
+        Some(FigureVariant::Rook) => 3,
+        Some(FigureVariant::Queen) => 4,
+        _ => 0,
+    };
+
+    to.file().index() as u16
+        | (to.rank().index() as u16) << 3
+        | (m.from.file().index() as u16) << 6
+        | (m.from.rank().index() as u16) << 9
+        | promotion << 12
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: u64, raw_move: u16, weight: u16) -> Vec<u8> {
+        let mut bytes = key.to_be_bytes().to_vec();
+        bytes.extend(raw_move.to_be_bytes());
+        bytes.extend(weight.to_be_bytes());
+        bytes.extend([0; 4]);
+        bytes
+    }
+
+    #[test]
+    fn looks_up_moves() {
+        let castled = "r1bqkbnr/pppp1ppp/2n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4";
+        let castled_key = Game::from_fen(castled).unwrap().zobrist_hash();
+        let bytes = [
+            entry(castled_key, 0x0107, 5),
+            entry(0x463b96181691fc9c, 0x031c, 1),
+            entry(0x463b96181691fc9c, 0x02db, 3),
+            // not a legal move in the position, it is left out
+            entry(0x463b96181691fc9c, 0x0000, 9),
+        ]
+        .concat();
+        let book = Book::from_bytes(&bytes).unwrap();
+        assert_eq!(book.len(), 4);
+
+        let d4 = "d2d4".parse().unwrap();
+        let e4 = "e2e4".parse().unwrap();
+        assert_eq!(book.moves(&Game::new()), [(d4, 3), (e4, 1)]);
+        // the king takes its rook in the book, the game castles to g1
+        let castling = Game::from_fen(castled).unwrap();
+        assert_eq!(book.moves(&castling), [("e1g1".parse().unwrap(), 5)]);
+
+        assert!(book.pick(&Game::new()).is_some());
+        assert_eq!(book.with_max_depth(0).pick(&Game::new()), None);
+        assert!(Book::from_bytes(&[0; 15]).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+use rand::Rng;
+
+use crate::{figure::FigureVariant, game::Game};
+
+/// number of the standard setup among the Chess960 starting positions
+pub const STANDARD_POSITION: u16 = 518;
+
+/// the figures of the back rank from a to h for one of the 960 starting positions,
+/// numbered the usual way so that 518 is the standard setup
+fn back_rank(index: u16) -> Result<[FigureVariant; 8], &'static str> {
+    if index >= 960 {
+        return Err("Chess960 positions are numbered from 0 to 959");
+    }
+
+    let mut rank: [Option<FigureVariant>; 8] = [None; 8];
+    let mut n = index as usize;
+
+    rank[(n % 4) * 2 + 1] = Some(FigureVariant::Bishop);
+    n /= 4;
+    rank[(n % 4) * 2] = Some(FigureVariant::Bishop);
+    n /= 4;
+
+    let mut place_on_empty = |nth: usize, variant: FigureVariant| {
+        let file = (0..8).filter(|f| rank[*f].is_none()).nth(nth).unwrap();
+        rank[file] = Some(variant);
+    };
+
+    place_on_empty(n % 6, FigureVariant::Queen);
+    n /= 6;
+
+    const KNIGHTS: [(usize, usize); 10] = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (0, 4),
+        (1, 2),
+        (1, 3),
+        (1, 4),
+        (2, 3),
+        (2, 4),
+        (3, 4),
+    ];
+    let (first, second) = KNIGHTS[n];
+    // the second knight goes among the fields left after placing the first one
+    place_on_empty(first, FigureVariant::Knight);
+    place_on_empty(second - 1, FigureVariant::Knight);
+
+    for variant in [
+        FigureVariant::Rook,
+        FigureVariant::King,
+        FigureVariant::Rook,
+    ] {
+        place_on_empty(0, variant);
+    }
+
+    Ok(rank.map(Option::unwrap))
+}
+
+impl Game {
+    /// a Chess960 game from the starting position with the given number, 0 to 959
+    pub fn new_chess960(index: u16) -> Result<Self, &'static str> {
+        let back_rank: String = back_rank(index)?.iter().map(|v| v.to_char()).collect();
+        let fen = format!(
+            "{}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1",
+            back_rank,
+            back_rank.to_ascii_uppercase()
+        );
+
+        let mut game = Game::from_fen(&fen)?;
+        game.chess960 = true;
+        Ok(game)
+    }
+
+    /// a Chess960 game from a random starting position
+    pub fn new_chess960_random() -> Self {
+        let index = rand::thread_rng().gen_range(0..960);
+        Self::new_chess960(index).expect("the index is in range")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn back_rank_letters(index: u16) -> String {
+        back_rank(index)
+            .unwrap()
+            .iter()
+            .map(|v| v.to_char().to_ascii_uppercase())
+            .collect()
+    }
+
+    #[test]
+    fn numbering() {
+        assert_eq!(back_rank_letters(0), "BBQNNRKR");
+        assert_eq!(back_rank_letters(STANDARD_POSITION), "RNBQKBNR");
+        assert_eq!(back_rank_letters(959), "RKRNNQBB");
+        assert!(back_rank(960).is_err());
+        assert!(Game::new_chess960(960).is_err());
+    }
+
+    #[test]
+    fn every_position_is_playable() {
+        let mut seen = std::collections::HashSet::new();
+        for index in 0..960 {
+            let letters = back_rank_letters(index);
+            let king = letters.find('K').unwrap();
+            let rooks: Vec<usize> = letters.match_indices('R').map(|(i, _)| i).collect();
+            assert!(rooks[0] < king && king < rooks[1], "{}", letters);
+            let bishops: Vec<usize> = letters.match_indices('B').map(|(i, _)| i).collect();
+            assert_ne!(bishops[0] % 2, bishops[1] % 2, "{}", letters);
+            assert!(seen.insert(letters));
+
+            let game = Game::new_chess960(index).unwrap();
+            assert!(game.is_chess960());
+            assert_eq!(game.validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn standard_position() {
+        let game = Game::new_chess960(STANDARD_POSITION).unwrap();
+        assert_eq!(game.board, Game::new().board);
+        assert!(game.is_chess960());
+    }
+}
@@ -0,0 +1,416 @@
+use std::{
+    fs,
+    io::stdin,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+use chess::{
+    Book, Engine, FigureColor, Game, GameState, Judgement, Move, RenderOptions, SearchInfo, Square,
+};
+use colored::Colorize;
+
+/// how deep the engine looks for a hint, and for how long at most
+const HINT_DEPTH: u32 = 4;
+const HINT_TIME: Duration = Duration::from_secs(2);
+
+/// reads one line from stdin, `None` once the input is closed
+pub fn read_line() -> Option<String> {
+    let mut buf = String::new();
+    match stdin().read_line(&mut buf) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(buf),
+    }
+}
+
+pub fn turn(game: &mut Game, input: &str) -> Result<(), &'static str> {
+    let m = input
+        .parse()
+        .or_else(|e| game.parse_san(input).or(Err(e)))?;
+    game.apply_move(m)
+}
+
+pub fn save(game: &Game, path: &str) -> Result<(), &'static str> {
+    fs::write(path, game.to_pgn()).or(Err("unable to write the file"))
+}
+
+pub fn load(path: &str) -> Result<Game, &'static str> {
+    let pgn = fs::read_to_string(path).or(Err("unable to read the file"))?;
+    Game::from_pgn(&pgn)
+}
+
+fn print_book(game: &Game, book: Option<&Book>) -> Result<(), &'static str> {
+    let moves = book
+        .ok_or("no opening book, start with --book FILE")?
+        .moves(game);
+    if moves.is_empty() {
+        println!("the position is not in the book");
+    }
+    let total: u32 = moves.iter().map(|(_, weight)| *weight as u32).sum();
+    for (m, weight) in moves {
+        println!(
+            "{:<8}{:>3}%",
+            game.san(&m),
+            weight as u32 * 100 / total.max(1)
+        );
+    }
+    Ok(())
+}
+
+/// annotates the game of a PGN file with the engine's opinion of every move,
+/// writing it to stdout or the `--output` file
+pub fn review(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    const USAGE: &str = "usage: review GAME.pgn [--depth N] [--output FILE]";
+    let mut path = None;
+    let mut depth = 3;
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => {
+                depth = args
+                    .next()
+                    .and_then(|d| d.parse().ok())
+                    .ok_or("--depth needs a number")?
+            }
+            "--output" => output = Some(args.next().ok_or("--output needs a file")?),
+            _ if arg.starts_with("--") => return Err(format!("unknown argument '{}'", arg)),
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let game = load(&path.ok_or(USAGE)?)?;
+    eprintln!(
+        "reviewing {} moves at depth {}",
+        game.history().len(),
+        depth
+    );
+    let review = Engine::new(depth).review(&game);
+
+    match output {
+        Some(output) => {
+            fs::write(&output, review.to_pgn()).or(Err("unable to write the file"))?;
+            eprintln!("saved to {}", output);
+        }
+        None => print!("{}", review.to_pgn()),
+    }
+    for (name, color) in [("white", FigureColor::White), ("black", FigureColor::Black)] {
+        let counts: Vec<String> = Judgement::ALL
+            .iter()
+            .map(|j| format!("{}: {}", j, review.count(color, *j)))
+            .collect();
+        eprintln!("{} {}", name, counts.join(", "));
+    }
+    Ok(())
+}
+
+fn print_info(game: &Game, info: &SearchInfo) {
+    let score = match info.mate_in() {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {:+}", info.score),
+    };
+    println!(
+        "depth {:<3}score {:<10}nodes {:<10}nps {:<9}pv {}",
+        info.depth,
+        score,
+        info.nodes,
+        info.nodes_per_second(),
+        game.san_line(&info.pv).join(" ")
+    );
+}
+
+/// searches the position deeper and deeper in the background until enter is pressed
+fn analyze(game: &Game, max_depth: u32) -> Result<(), &'static str> {
+    if game.is_over() {
+        return Err("the game is over");
+    }
+    println!("analyzing, press enter to stop");
+
+    let stop = AtomicBool::new(false);
+    thread::scope(|scope| {
+        let search = scope.spawn(|| {
+            let deepest =
+                Engine::new(1).analyze(game, max_depth, &stop, |info| print_info(game, info));
+            if !stop.load(Ordering::Relaxed) {
+                println!("analysis done, press enter");
+            }
+            deepest
+        });
+
+        read_line();
+        stop.store(true, Ordering::Relaxed);
+        if let Ok(Some(info)) = search.join() {
+            if let Some(m) = info.best_move() {
+                println!("best move {}", game.san(&m));
+            }
+        }
+    });
+    Ok(())
+}
+
+/// what a hint shows, first the figure to move and the whole move when asked again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hint {
+    Figure(Move),
+    Move(Move),
+}
+
+/// the hints a player may still ask for and the one given last
+pub struct Hints {
+    limit: Option<u32>,
+    /// `None` for as many as wanted
+    left: Option<u32>,
+    /// the move suggested and after how many half-moves, asking again in the same
+    /// position reveals the whole move without using up another hint
+    given: Option<(usize, Move)>,
+}
+
+impl Hints {
+    pub fn new(limit: Option<u32>) -> Self {
+        Hints {
+            limit,
+            left: limit,
+            given: None,
+        }
+    }
+
+    /// starts over for another game
+    pub fn reset(&mut self) {
+        *self = Self::new(self.limit);
+    }
+
+    /// searches the engine's move, asking again in the same position reveals the whole
+    /// move without using up another hint
+    pub fn next(&mut self, game: &Game) -> Result<Hint, &'static str> {
+        let ply = game.history().len();
+        if let Some((_, m)) = self.given.filter(|(at, _)| *at == ply) {
+            return Ok(Hint::Move(m));
+        }
+
+        if self.left == Some(0) {
+            return Err("no hints left in this game");
+        }
+        let info = Engine::new(HINT_DEPTH)
+            .analyze_for(game, HINT_DEPTH, HINT_TIME, |_| ())
+            .ok_or("the game is over")?;
+        let m = info.best_move().ok_or("the game is over")?;
+        self.left = self.left.map(|left| left - 1);
+        self.given = Some((ply, m));
+        Ok(Hint::Figure(m))
+    }
+
+    /// marks the figure to move on the board, or the whole move the second time, and
+    /// returns what to tell the player about it
+    pub fn give(
+        &mut self,
+        game: &Game,
+        options: &mut RenderOptions,
+    ) -> Result<String, &'static str> {
+        match self.next(game)? {
+            Hint::Move(m) => {
+                options.hint = Some(m);
+                Ok(format!("hint: {}", game.san(&m)))
+            }
+            Hint::Figure(m) => {
+                options.selected = Some(m.from);
+                let figure = game.board.get(&m.from).ok_or("the engine has no move")?;
+                let figure = match options.theme.ascii {
+                    true => figure.get_letter(),
+                    false => figure.get_symbol(),
+                };
+                let left = match self.left {
+                    Some(left) => format!(", hints left: {}", left),
+                    None => String::new(),
+                };
+                Ok(format!(
+                    "hint: move the {} on {}, ask again for the whole move{}",
+                    figure, m.from, left
+                ))
+            }
+        }
+    }
+}
+
+/// the engine playing one side against the user, see `--bot`
+pub struct Bot {
+    pub engine: Engine,
+    pub color: FigureColor,
+}
+
+impl Bot {
+    /// plays the engine's move if it is its turn and returns it in SAN
+    pub fn play(&self, game: &mut Game) -> Option<String> {
+        if game.is_over() || game.side_to_move() != self.color {
+            return None;
+        }
+        let m = self.engine.best_move(game)?;
+        let san = game.san(&m);
+        game.apply_move(m).ok()?;
+        Some(san)
+    }
+}
+
+fn command(
+    game: &mut Game,
+    options: &mut RenderOptions,
+    book: Option<&Book>,
+    hints: &mut Hints,
+    input: &str,
+) -> Result<(), &'static str> {
+    let mut words = input.split_whitespace();
+
+    match words.next().unwrap_or_default() {
+        "eval" => println!("{}", game.evaluate()),
+        "book" => print_book(game, book)?,
+        "hint" => println!("{}", hints.give(game, options)?),
+        "analyze" => {
+            let depth = match words.next() {
+                Some(depth) => depth.parse().or(Err("unable to parse the depth"))?,
+                None => 64,
+            };
+            analyze(game, depth)?
+        }
+        "fen" => println!("{}", game.to_fen()),
+        "pgn" => println!("{}", game.to_pgn()),
+        "save" => {
+            let path = words.next().ok_or("missing file name")?;
+            save(game, path)?;
+            println!("saved to {}", path);
+        }
+        "load" => {
+            *game = load(words.next().ok_or("missing file name")?)?;
+            hints.reset();
+        }
+        "" => (),
+        field
+            if field
+                .parse()
+                .is_ok_and(|c: Square| game.board.contains_key(&c)) =>
+        {
+            options.selected = field.parse().ok()
+        }
+        _ => turn(game, input)?,
+    }
+    Ok(())
+}
+
+fn print_turn(game: &Game, options: &RenderOptions) {
+    println!("{}", "----------------------------".cyan());
+    println!("{}'s turn", game.side_to_move());
+    if let GameState::Check(_) = game.state() {
+        println!("{}", "check!".yellow());
+    }
+    println!("{}", game.render(options));
+    println!(
+        "input a move like 'e2e4' or 'Nf3', a field to see where its figure can go, or one of \
+         'hint', 'eval', 'analyze [depth]', 'book', 'fen', 'pgn', 'save FILE', 'load FILE', 'quit': "
+    );
+}
+
+pub fn print_result(game: &Game, options: &RenderOptions) {
+    println!("{}", "----------------------------".cyan());
+    println!("{}", game.render(options));
+    match game.state() {
+        GameState::Checkmate(color) => println!("checkmate, {} wins", color.opposite()),
+        GameState::Stalemate(_) => println!("stalemate, it's a draw"),
+        GameState::OutOfTime(color) => {
+            println!("{} ran out of time, {} wins", color, color.opposite())
+        }
+        _ => (),
+    }
+}
+
+pub fn game_loop(
+    game: &mut Game,
+    options: &RenderOptions,
+    book: Option<&Book>,
+    hints: &mut Hints,
+    bot: Option<&Bot>,
+) {
+    let mut options = *options;
+
+    while !game.is_over() {
+        if let Some(san) = bot.and_then(|bot| bot.play(game)) {
+            println!("{} plays {}", game.side_to_move().opposite().name(), san);
+            continue;
+        }
+        print_turn(game, &options);
+        options.selected = None;
+        options.hint = None;
+
+        let Some(input) = read_line() else {
+            return;
+        };
+        if input.trim() == "quit" {
+            return;
+        }
+
+        if let Err(e) = command(game, &mut options, book, hints, &input) {
+            println!("{}", format!("err: {}", e).red());
+        }
+    }
+    print_result(game, &options);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hints_run_out() {
+        let mut game = Game::new();
+        let mut hints = Hints::new(Some(1));
+        let Ok(Hint::Figure(m)) = hints.next(&game) else {
+            panic!("the first hint shows the figure");
+        };
+        assert!(game.legal_moves().contains(&m));
+        // asking again in the same position is free
+        assert_eq!(hints.next(&game), Ok(Hint::Move(m)));
+
+        game.apply_move(m).unwrap();
+        assert_eq!(hints.next(&game), Err("no hints left in this game"));
+    }
+
+    #[test]
+    fn hints_mark_the_board() {
+        let game = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let mut hints = Hints::new(None);
+        let mut options = RenderOptions::default();
+        hints.give(&game, &mut options).unwrap();
+        assert_eq!(options.selected, Some("a1".parse().unwrap()));
+        assert_eq!(options.hint, None);
+        assert_eq!(
+            hints.give(&game, &mut options),
+            Ok("hint: Ra8#".to_string())
+        );
+        assert_eq!(options.hint, Some("a1a8".parse().unwrap()));
+    }
+
+    #[test]
+    fn loading_resets_hints() {
+        let path = std::env::temp_dir().join(format!("chess-hints-{}.pgn", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut game = Game::new();
+        save(&game, path).unwrap();
+
+        let mut hints = Hints::new(Some(1));
+        let mut options = RenderOptions::default();
+        command(&mut game, &mut options, None, &mut hints, "hint").unwrap();
+        command(&mut game, &mut options, None, &mut hints, "e2e4").unwrap();
+        assert!(command(&mut game, &mut options, None, &mut hints, "hint").is_err());
+
+        command(
+            &mut game,
+            &mut options,
+            None,
+            &mut hints,
+            &format!("load {}", path),
+        )
+        .unwrap();
+        fs::remove_file(path).unwrap();
+        assert!(game.history().is_empty());
+        assert!(matches!(hints.next(&game), Ok(Hint::Figure(_))));
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use crate::figure::FigureColor;
+
+/// a chess clock, counting down from `limit` when there is one, otherwise just
+/// counting how long each side has been thinking
+#[derive(Debug, Clone)]
+pub struct Clock {
+    limit: Option<Duration>,
+    increment: Duration,
+    used: [Duration; 2],
+    moves: [u32; 2],
+    running: Option<(FigureColor, Instant)>,
+}
+
+/// the longest time control [`Clock::from_time_control`] accepts, a day
+const MAX_MINUTES: f64 = 24.0 * 60.0;
+/// the most seconds of increment, an hour
+const MAX_INCREMENT: u64 = 3600;
+
+impl Clock {
+    pub fn new(limit: Option<Duration>, increment: Duration) -> Self {
+        Clock {
+            limit,
+            increment,
+            used: [Duration::ZERO; 2],
+            moves: [0; 2],
+            running: None,
+        }
+    }
+
+    /// a clock without a time limit
+    pub fn unlimited() -> Self {
+        Self::new(None, Duration::ZERO)
+    }
+
+    /// parses a time control like `5` or `3+2`, minutes plus seconds of increment
+    pub fn from_time_control(control: &str) -> Result<Self, &'static str> {
+        let (minutes, increment) = control.split_once('+').unwrap_or((control, "0"));
+        let minutes: f64 = minutes
+            .trim()
+            .parse()
+            .or(Err("unable to parse the minutes of the time control"))?;
+        let increment: u64 = increment
+            .trim()
+            .parse()
+            .or(Err("unable to parse the increment of the time control"))?;
+
+        if !minutes.is_finite() {
+            return Err("unable to parse the minutes of the time control");
+        }
+        if minutes <= 0.0 {
+            return Err("the time control has to be longer than zero minutes");
+        }
+        if minutes > MAX_MINUTES {
+            return Err("the time control can be a day long at most");
+        }
+        if increment > MAX_INCREMENT {
+            return Err("the increment can be an hour at most");
+        }
+
+        Ok(Self::new(
+            Some(Duration::from_secs_f64(minutes * 60.0)),
+            Duration::from_secs(increment),
+        ))
+    }
+
+    pub fn limit(&self) -> Option<Duration> {
+        self.limit
+    }
+
+    pub fn increment(&self) -> Duration {
+        self.increment
+    }
+
+    /// starts or keeps running the clock of `color`, stopping the other one
+    pub fn start(&mut self, color: FigureColor) {
+        match self.running {
+            Some((running, _)) if running == color => (),
+            Some(_) => self.press(),
+            None => self.running = Some((color, Instant::now())),
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Some((color, since)) = self.running.take() {
+            self.used[color.index()] += since.elapsed();
+        }
+    }
+
+    /// ends the turn of the running side, adds its increment and starts the opponent's clock
+    pub fn press(&mut self) {
+        if let Some((color, since)) = self.running.take() {
+            self.used[color.index()] += since.elapsed();
+            self.moves[color.index()] += 1;
+            self.running = Some((color.opposite(), Instant::now()));
+        }
+    }
+
+    pub fn running(&self) -> Option<FigureColor> {
+        self.running.map(|(color, _)| color)
+    }
+
+    /// time spent thinking by `color`
+    pub fn used(&self, color: FigureColor) -> Duration {
+        let mut used = self.used[color.index()];
+        if let Some((running, since)) = self.running {
+            if running == color {
+                used += since.elapsed();
+            }
+        }
+        used
+    }
+
+    /// time left for `color`, `None` for clocks without a limit
+    pub fn remaining(&self, color: FigureColor) -> Option<Duration> {
+        self.limit.map(|limit| {
+            let earned = self.increment * self.moves[color.index()];
+            (limit + earned).saturating_sub(self.used(color))
+        })
+    }
+
+    /// what the clock face of `color` shows, the remaining time or the time used
+    pub fn shown(&self, color: FigureColor) -> Duration {
+        self.remaining(color).unwrap_or_else(|| self.used(color))
+    }
+
+    /// the side that ran out of time, if any
+    pub fn flagged(&self) -> Option<FigureColor> {
+        [FigureColor::White, FigureColor::Black]
+            .into_iter()
+            .find(|color| self.remaining(*color) == Some(Duration::ZERO))
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+/// formats a clock face as `mm:ss`, or `h:mm:ss` for long games
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+impl Display for Clock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "white {} | black {}",
+            format_duration(self.shown(FigureColor::White)),
+            format_duration(self.shown(FigureColor::Black))
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_controls() {
+        let clock = Clock::from_time_control("3+2").unwrap();
+        assert_eq!(clock.limit(), Some(Duration::from_secs(180)));
+        assert_eq!(clock.increment(), Duration::from_secs(2));
+        let clock = Clock::from_time_control("0.5").unwrap();
+        assert_eq!(clock.limit(), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn bad_time_controls() {
+        for control in [
+            "", "0", "-1", "nan", "NaN+0", "inf", "-inf", "1e300", "1441", "5+x", "5+3601",
+        ] {
+            assert!(Clock::from_time_control(control).is_err(), "{}", control);
+        }
+    }
+}
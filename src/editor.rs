@@ -0,0 +1,180 @@
+use chess::{Figure, FigureColor, FigureVariant, Game, RenderOptions, Setup};
+use colored::Colorize;
+
+use crate::cli::read_line;
+
+const HELP: &str = "\
+place {figure} {field}  put a figure on a field, uppercase for white, eg 'place Q d1'
+remove {field}          take the figure off a field
+clear                   empty the board
+start                   the standard starting position
+turn w|b                pick the side to move
+castle {rights}         castling rights like 'KQkq', 'HAha' or '-'
+ep {field}|-            the field a pawn just skipped over
+fen {fen}               load a whole position
+done                    check the position and start playing";
+
+fn parse_figure(code: &str) -> Result<Figure, &'static str> {
+    let mut chars = code.chars();
+    let letter = chars.next().ok_or("missing figure")?;
+    if chars.next().is_some() {
+        return Err("figures are a single letter, eg 'Q' or 'n'");
+    }
+    let variant = FigureVariant::from_char(letter).ok_or("unknown figure letter")?;
+    let color = if letter.is_ascii_uppercase() {
+        FigureColor::White
+    } else {
+        FigureColor::Black
+    };
+    Ok(Figure::new(color, variant))
+}
+
+fn command(setup: &mut Setup, input: &str) -> Result<(), &'static str> {
+    let mut words = input.split_whitespace();
+    let name = words.next().unwrap_or_default();
+    let mut arg = || words.next().ok_or("missing argument, type 'help'");
+
+    match name {
+        "place" => {
+            let figure = parse_figure(arg()?)?;
+            setup.place(arg()?.parse()?, figure);
+            Ok(())
+        }
+        "remove" => {
+            setup
+                .remove(arg()?.parse()?)
+                .ok_or("theres no figure on that field")?;
+            Ok(())
+        }
+        "clear" => {
+            setup.clear();
+            Ok(())
+        }
+        "start" => {
+            *setup = Setup::from_game(&Game::new());
+            Ok(())
+        }
+        "turn" => {
+            setup.side_to_move = match arg()? {
+                "w" | "white" => FigureColor::White,
+                "b" | "black" => FigureColor::Black,
+                _ => return Err("the side to move has to be 'w' or 'b'"),
+            };
+            Ok(())
+        }
+        "castle" => setup.set_castling(arg()?),
+        "ep" => {
+            setup.en_passant = match arg()? {
+                "-" => None,
+                field => Some(field.parse()?),
+            };
+            Ok(())
+        }
+        "fen" => {
+            let fen = input.trim_start().trim_start_matches("fen").trim();
+            *setup = Setup::from_game(&Game::from_fen(fen)?);
+            Ok(())
+        }
+        "" => Ok(()),
+        _ => Err("unknown command, type 'help'"),
+    }
+}
+
+/// lets the user put a position together, `None` when the input ends before it is done
+pub fn setup_loop(start: &Game, options: &RenderOptions) -> Option<Game> {
+    let mut setup = Setup::from_game(start);
+    // a position being set up has no moves or captures to show
+    let options = &RenderOptions {
+        panel: false,
+        ..*options
+    };
+    println!("{}", HELP);
+
+    loop {
+        println!("{}", "----------------------------".cyan());
+        println!("{}", setup.preview().render(options));
+        println!(
+            "{} to move, {}",
+            setup.side_to_move,
+            setup.preview().to_fen()
+        );
+        println!("setup: ");
+
+        let input = read_line()?;
+        let result = match input.trim() {
+            "help" => {
+                println!("{}", HELP);
+                Ok(())
+            }
+            "done" => match setup.clone().into_game() {
+                Ok(game) => return Some(game),
+                Err(errors) => {
+                    for e in errors {
+                        println!("{}", format!("err: {}", e).red());
+                    }
+                    Ok(())
+                }
+            },
+            input => command(&mut setup, input),
+        };
+
+        if let Err(e) = result {
+            println!("{}", format!("err: {}", e).red());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fen(commands: &[&str]) -> Result<String, &'static str> {
+        let mut setup = Setup::empty();
+        for input in commands {
+            command(&mut setup, input)?;
+        }
+        Ok(setup.preview().to_fen())
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(
+            fen(&[
+                "place K e1",
+                "place k e8",
+                "place R h1",
+                "castle K",
+                "turn b"
+            ]),
+            Ok("4k3/8/8/8/8/8/8/4K2R b K - 0 1".to_string())
+        );
+        assert_eq!(
+            fen(&["start", "remove e2", "place P e4", "turn black", "ep e3"]),
+            Ok("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".to_string())
+        );
+        assert_eq!(
+            fen(&["start", "clear", "", "fen 8/8/4k3/8/8/3K4/8/8 w - - 3 20"]),
+            Ok("8/8/4k3/8/8/3K4/8/8 w - - 3 20".to_string())
+        );
+        assert_eq!(fen(&["start", "ep e3", "ep -"]), Ok(Game::new().to_fen()));
+    }
+
+    #[test]
+    fn bad_commands() {
+        assert_eq!(fen(&["place"]), Err("missing argument, type 'help'"));
+        assert_eq!(fen(&["place X e4"]), Err("unknown figure letter"));
+        assert_eq!(
+            fen(&["place QQ e4"]),
+            Err("figures are a single letter, eg 'Q' or 'n'")
+        );
+        assert!(fen(&["place Q e9"]).is_err());
+        assert_eq!(fen(&["remove e4"]), Err("theres no figure on that field"));
+        assert_eq!(
+            fen(&["turn x"]),
+            Err("the side to move has to be 'w' or 'b'")
+        );
+        assert!(fen(&["castle K"]).is_err());
+        assert!(fen(&["fen nonsense"]).is_err());
+        assert_eq!(fen(&["jump"]), Err("unknown command, type 'help'"));
+    }
+}
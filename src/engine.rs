@@ -0,0 +1,498 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    book::Book,
+    eval::figure_value,
+    game::{Game, GameState},
+    moves::Move,
+};
+
+/// score of the side to move being checkmated, mates in fewer moves score further from zero
+pub const MATE: i32 = 100_000;
+
+/// scores beyond this are mates found by the search rather than evaluations
+pub(crate) const MATE_BOUND: i32 = MATE - 1000;
+
+/// the level of the full search, see [`Engine::with_level`]
+pub const MAX_LEVEL: u8 = 20;
+
+/// the deepest [`Engine::with_level`] lets the engine search, at [`MAX_LEVEL`]
+pub const MAX_LEVEL_DEPTH: u32 = 1 + MAX_LEVEL as u32 / 6;
+
+/// the ratings [`Engine::with_elo`] maps onto the levels, 100 points apart
+pub const MIN_ELO: u32 = 600;
+pub const MAX_ELO: u32 = MIN_ELO + 100 * (MAX_LEVEL as u32 - 1);
+
+/// picks moves with an alpha-beta minimax search to a fixed depth, scoring the positions
+/// at the end with [`Game::evaluate`]
+#[derive(Debug, Clone)]
+pub struct Engine {
+    depth: u32,
+    book: Option<Book>,
+    level: u8,
+}
+
+/// what the search found after looking at a position to some depth
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchInfo {
+    pub depth: u32,
+    /// centipawns for the side to move, or a mate, see [`SearchInfo::mate_in`]
+    pub score: i32,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    /// the principal variation, the best move and the replies both sides are expected to play
+    pub pv: Vec<Move>,
+}
+
+impl SearchInfo {
+    pub fn best_move(&self) -> Option<Move> {
+        self.pv.first().copied()
+    }
+
+    /// moves until the side to move mates, negative when it gets mated
+    pub fn mate_in(&self) -> Option<i32> {
+        if self.score > MATE_BOUND {
+            Some((MATE - self.score + 1) / 2)
+        } else if self.score < -MATE_BOUND {
+            Some(-(MATE + self.score) / 2)
+        } else {
+            None
+        }
+    }
+
+    pub fn nodes_per_second(&self) -> u64 {
+        (self.nodes as f64 / self.elapsed.as_secs_f64().max(0.001)) as u64
+    }
+}
+
+impl Engine {
+    /// an engine looking `depth` half-moves ahead
+    pub fn new(depth: u32) -> Self {
+        Engine {
+            depth: depth.max(1),
+            book: None,
+            level: MAX_LEVEL,
+        }
+    }
+
+    /// plays like a weaker player, from 1, hardly better than random moves, to [`MAX_LEVEL`]
+    ///
+    /// lower levels search less deep and fewer nodes, misjudge positions by up to a few
+    /// pawns and now and then play a random move, the depth is capped at 1 for the lowest
+    /// levels up to [`MAX_LEVEL_DEPTH`]
+    pub fn with_level(mut self, level: u8) -> Self {
+        self.level = level.clamp(1, MAX_LEVEL);
+        self.depth = self.depth.min(1 + self.level as u32 / 6);
+        self
+    }
+
+    /// the level for a rating between [`MIN_ELO`] and [`MAX_ELO`], like the `UCI_Elo` option
+    /// of other engines, the ratings are a rough guide and were not measured
+    pub fn with_elo(self, elo: u32) -> Self {
+        let level = (elo.clamp(MIN_ELO, MAX_ELO) - MIN_ELO) / 100 + 1;
+        self.with_level(level as u8)
+    }
+
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// nodes a search may visit before it stops deepening, without a limit at full strength
+    fn max_nodes(&self) -> Option<u64> {
+        let level = self.level as u64;
+        (self.level < MAX_LEVEL).then(|| 25 * level * level)
+    }
+
+    /// the most centipawns the evaluation can be off by
+    fn noise(&self) -> i32 {
+        (MAX_LEVEL - self.level) as i32 * 12
+    }
+
+    /// how likely a random move is, 45% at level 1 and none at full strength
+    fn mistake_chance(&self) -> f64 {
+        let weakness = (MAX_LEVEL - self.level) as f64;
+        weakness * weakness / 800.0
+    }
+
+    /// plays from the book while the position is in it, see [`Book::pick`]
+    pub fn with_book(mut self, book: Book) -> Self {
+        self.book = Some(book);
+        self
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// a book move when there is one, the best move found by the search otherwise
+    pub fn best_move(&self, game: &Game) -> Option<Move> {
+        self.book
+            .as_ref()
+            .and_then(|book| book.pick(game))
+            .or_else(|| self.search(game).map(|(m, _)| m))
+    }
+
+    /// the best move and its score in centipawns for the side to move,
+    /// `None` when the game is over
+    ///
+    /// below full strength the move may be a random one, scored by the static evaluation
+    pub fn search(&self, game: &Game) -> Option<(Move, i32)> {
+        // a random move needs no search, so the dice are rolled first
+        let mut rng = rand::thread_rng();
+        if rng.gen_bool(self.mistake_chance()) {
+            if let Some(m) = game.legal_moves().choose(&mut rng) {
+                let mut after = game.clone();
+                after.play(*m);
+                return Some((*m, after.evaluate().relative(game.side_to_move())));
+            }
+        }
+        let info = self.analyze(game, self.depth, &AtomicBool::new(false), |_| ())?;
+        Some((info.best_move()?, info.score))
+    }
+
+    /// searches one half-move deeper at a time, up to `max_depth` or until `stop` gets set,
+    /// and hands every finished depth to `report`, the deepest one is returned
+    ///
+    /// the first depth is always finished, so there is a move even when stopped right away
+    pub fn analyze(
+        &self,
+        game: &Game,
+        max_depth: u32,
+        stop: &AtomicBool,
+        report: impl FnMut(&SearchInfo),
+    ) -> Option<SearchInfo> {
+        self.deepen(game, max_depth, stop, None, report)
+    }
+
+    /// like [`Engine::analyze`], stopping once `time` is up instead
+    pub fn analyze_for(
+        &self,
+        game: &Game,
+        max_depth: u32,
+        time: Duration,
+        report: impl FnMut(&SearchInfo),
+    ) -> Option<SearchInfo> {
+        let never = AtomicBool::new(false);
+        self.deepen(game, max_depth, &never, Some(Instant::now() + time), report)
+    }
+
+    fn deepen(
+        &self,
+        game: &Game,
+        max_depth: u32,
+        stop: &AtomicBool,
+        deadline: Option<Instant>,
+        mut report: impl FnMut(&SearchInfo),
+    ) -> Option<SearchInfo> {
+        if game.is_over() {
+            return None;
+        }
+
+        let start = Instant::now();
+        let mut search = Search {
+            nodes: 0,
+            stop,
+            deadline,
+            root_depth: 0,
+            can_stop: false,
+            stopped: false,
+            max_nodes: self.max_nodes(),
+            noise: self.noise(),
+            seed: rand::thread_rng().gen(),
+        };
+        let mut deepest: Option<SearchInfo> = None;
+
+        for depth in 1..=max_depth.max(1) {
+            search.root_depth = depth;
+            search.can_stop = deepest.is_some();
+            let hint = deepest.as_ref().map_or(Vec::new(), |info| info.pv.clone());
+            let mut pv = Vec::new();
+            let score = search.negamax(game, depth, -MATE - 1, MATE + 1, &hint, &mut pv);
+            if search.stopped {
+                break;
+            }
+
+            let info = SearchInfo {
+                depth,
+                score,
+                nodes: search.nodes,
+                elapsed: start.elapsed(),
+                pv,
+            };
+            report(&info);
+            // searching deeper cannot find a shorter mate
+            let mate = info.mate_in().is_some();
+            deepest = Some(info);
+            if mate {
+                break;
+            }
+        }
+
+        deepest
+    }
+}
+
+struct Search<'a> {
+    nodes: u64,
+    stop: &'a AtomicBool,
+    deadline: Option<Instant>,
+    /// the depth searched from the root, without extensions the ply is what is left of it
+    root_depth: u32,
+    can_stop: bool,
+    stopped: bool,
+    max_nodes: Option<u64>,
+    /// see [`Engine::noise`], `seed` makes every search misjudge differently
+    noise: i32,
+    seed: u64,
+}
+
+impl Search<'_> {
+    /// the error made in judging a position, the same whenever it comes up in one search
+    /// so that the scores of its lines stay comparable
+    fn noise(&self, game: &Game) -> i32 {
+        if self.noise == 0 {
+            return 0;
+        }
+        // splitmix64, the hashes of similar positions would give similar errors otherwise
+        let mut x = game.zobrist_hash() ^ self.seed;
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        x ^= x >> 31;
+        (x % (2 * self.noise as u64 + 1)) as i32 - self.noise
+    }
+
+    /// `hint` is the principal variation of the previous depth, searched first so that
+    /// the rest gets cut off sooner, `pv` gets the best line found from this position
+    fn negamax(
+        &mut self,
+        game: &Game,
+        depth: u32,
+        mut alpha: i32,
+        beta: i32,
+        hint: &[Move],
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        self.nodes += 1;
+        if self.can_stop && self.nodes.is_multiple_of(1024) {
+            self.stopped = self.stop.load(Ordering::Relaxed)
+                || self
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline);
+        }
+        if self.can_stop && self.max_nodes.is_some_and(|max| self.nodes > max) {
+            self.stopped = true;
+        }
+        if self.stopped {
+            return 0;
+        }
+
+        match game.state() {
+            GameState::Checkmate(_) => return -MATE + (self.root_depth - depth) as i32,
+            GameState::Stalemate(_) => return 0,
+            _ => (),
+        }
+        if depth == 0 {
+            return game.evaluate().relative(game.side_to_move()) + self.noise(game);
+        }
+
+        let mut moves = ordered_moves(game);
+        if let Some(i) = moves.iter().position(|m| hint.first() == Some(m)) {
+            let first = moves.remove(i);
+            moves.insert(0, first);
+        }
+
+        for m in moves {
+            let mut after = game.clone();
+            after.play(m);
+            let child_hint = if hint.first() == Some(&m) {
+                &hint[1..]
+            } else {
+                &[]
+            };
+
+            let mut line = Vec::new();
+            let score = -self.negamax(&after, depth - 1, -beta, -alpha, child_hint, &mut line);
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(m);
+                pv.extend(line);
+            }
+        }
+
+        alpha
+    }
+}
+
+/// legal moves with promotions and the captures of the most valuable figures first,
+/// so that the search can cut off the rest sooner
+fn ordered_moves(game: &Game) -> Vec<Move> {
+    let mut moves = game.legal_moves();
+    moves.sort_by_key(|m| {
+        let taken = game
+            .board
+            .get(&m.to)
+            .filter(|f| f.color != game.side_to_move())
+            .map_or(0, |f| figure_value(f.variant));
+        let promotion = m.promotion.map_or(0, figure_value);
+        -(taken + promotion)
+    });
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(fen: &str) -> Game {
+        Game::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn mates_in_one() {
+        let back_rank = game("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let (m, score) = Engine::new(2).search(&back_rank).unwrap();
+        assert_eq!(m.to_string(), "a1a8");
+        assert_eq!(score, MATE - 1);
+    }
+
+    #[test]
+    fn mates_in_two() {
+        // checking right away lets the king out, a quiet rook move to the 7th rank mates next
+        let ladder = game("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1");
+        for depth in [3, 4] {
+            let (m, score) = Engine::new(depth).search(&ladder).unwrap();
+            assert_eq!(score, MATE - 3);
+            assert!(["a2a7", "b1b7"].contains(&m.to_string().as_str()));
+        }
+        assert!(Engine::new(2).search(&ladder).unwrap().1 < MATE_BOUND);
+    }
+
+    #[test]
+    fn sees_getting_mated() {
+        let mated = game("7k/R7/8/8/8/8/8/1R4K1 b - - 1 1");
+        let (m, score) = Engine::new(2).search(&mated).unwrap();
+        assert_eq!(m.to_string(), "h8g8");
+        assert_eq!(score, -MATE + 2);
+    }
+
+    #[test]
+    fn nothing_to_search_once_over() {
+        let mate = game("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1");
+        assert_eq!(Engine::new(3).search(&mate), None);
+        let stalemate = game("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+        assert_eq!(Engine::new(3).search(&stalemate), None);
+    }
+
+    #[test]
+    fn analyze_reports_mates() {
+        let never = AtomicBool::new(false);
+        let ladder = game("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1");
+        let mut depths = Vec::new();
+        let info = Engine::new(4)
+            .analyze(&ladder, 4, &never, |info| depths.push(info.depth))
+            .unwrap();
+        // deeper searches cannot find a shorter mate
+        assert_eq!(depths, [1, 2, 3]);
+        assert_eq!(info.mate_in(), Some(2));
+        assert_eq!(info.pv.len(), 3);
+        assert_eq!(
+            ladder
+                .san_line(&info.pv)
+                .last()
+                .map(|san| san.ends_with('#')),
+            Some(true)
+        );
+
+        let mated = game("7k/R7/8/8/8/8/8/1R4K1 b - - 1 1");
+        let info = Engine::new(2).analyze(&mated, 2, &never, |_| ()).unwrap();
+        assert_eq!(info.mate_in(), Some(-1));
+
+        let quiet = Engine::new(2)
+            .analyze(&Game::new(), 2, &never, |_| ())
+            .unwrap();
+        assert_eq!(quiet.mate_in(), None);
+    }
+
+    #[test]
+    fn analyze_stops() {
+        // the first depth is always searched, the flag is only looked at every 1024 nodes
+        let stop = AtomicBool::new(true);
+        let info = Engine::new(8)
+            .analyze(&Game::new(), 8, &stop, |_| ())
+            .unwrap();
+        assert!((1..=2).contains(&info.depth));
+        assert!(info.best_move().is_some());
+    }
+
+    #[test]
+    fn levels_clamp() {
+        assert_eq!(Engine::new(4).with_level(0).level(), 1);
+        assert_eq!(Engine::new(4).with_level(255).level(), MAX_LEVEL);
+        assert_eq!(Engine::new(4).with_elo(0).level(), 1);
+        assert_eq!(Engine::new(4).with_elo(MIN_ELO).level(), 1);
+        assert_eq!(Engine::new(4).with_elo(MAX_ELO).level(), MAX_LEVEL);
+        assert_eq!(Engine::new(4).with_elo(u32::MAX).level(), MAX_LEVEL);
+    }
+
+    #[test]
+    fn full_strength() {
+        let engine = Engine::new(4).with_level(MAX_LEVEL);
+        assert_eq!(engine.noise(), 0);
+        assert_eq!(engine.mistake_chance(), 0.0);
+        assert_eq!(engine.max_nodes(), None);
+        assert_eq!(engine.depth(), 4);
+        // a new engine plays at full strength
+        assert_eq!(Engine::new(4).level(), MAX_LEVEL);
+        assert_eq!(Engine::new(4).max_nodes(), None);
+    }
+
+    #[test]
+    fn levels_cap_the_depth() {
+        assert_eq!(Engine::new(MAX_LEVEL_DEPTH).with_level(1).depth(), 1);
+        assert_eq!(Engine::new(MAX_LEVEL_DEPTH).with_level(12).depth(), 3);
+        assert_eq!(
+            Engine::new(MAX_LEVEL_DEPTH).with_level(MAX_LEVEL).depth(),
+            MAX_LEVEL_DEPTH
+        );
+        // the level never searches deeper than asked
+        assert_eq!(Engine::new(2).with_level(MAX_LEVEL).depth(), 2);
+        assert_eq!(
+            Engine::new(9).with_level(MAX_LEVEL).depth(),
+            MAX_LEVEL_DEPTH
+        );
+    }
+
+    #[test]
+    fn weaker_levels_are_weaker() {
+        let engines: Vec<Engine> = (MIN_ELO..=MAX_ELO + 100)
+            .step_by(50)
+            .map(|elo| Engine::new(MAX_LEVEL_DEPTH).with_elo(elo))
+            .collect();
+        for pair in engines.windows(2) {
+            let (lower, higher) = (&pair[0], &pair[1]);
+            assert!(lower.level() <= higher.level());
+            assert!(lower.depth() <= higher.depth());
+            assert!(lower.noise() >= higher.noise());
+            assert!(lower.mistake_chance() >= higher.mistake_chance());
+            let nodes = |engine: &Engine| engine.max_nodes().unwrap_or(u64::MAX);
+            assert!(nodes(lower) <= nodes(higher));
+        }
+        let levels: Vec<u8> = [600, 699, 700, 1500, 2499, 2500]
+            .iter()
+            .map(|elo| Engine::new(1).with_elo(*elo).level())
+            .collect();
+        assert_eq!(levels, [1, 1, 2, 10, 19, 20]);
+    }
+}
@@ -0,0 +1,156 @@
+use std::str::FromStr;
+
+use crate::{game::Game, moves::Move};
+
+/// a line of Extended Position Description, the first four FEN fields followed by
+/// operations like `bm Nf3; id "opening 1";`
+#[derive(Debug, Clone)]
+pub struct Epd {
+    pub game: Game,
+    operations: Vec<(String, String)>,
+}
+
+impl Epd {
+    /// the operands of the first operation with the opcode, without the quotes of a string
+    pub fn operation(&self, opcode: &str) -> Option<&str> {
+        self.operations
+            .iter()
+            .find(|(o, _)| o == opcode)
+            .map(|(_, operands)| operands.as_str())
+    }
+
+    /// the `id` operation naming the position
+    pub fn id(&self) -> Option<&str> {
+        self.operation("id")
+    }
+
+    /// the moves in SAN of an operation like `bm` or `am`, none when the operation is missing
+    pub fn moves(&self, opcode: &str) -> Result<Vec<Move>, &'static str> {
+        self.operation(opcode)
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(|san| self.game.parse_san(san))
+            .collect()
+    }
+
+    pub fn operations(&self) -> &[(String, String)] {
+        &self.operations
+    }
+}
+
+/// splits the operations at the semicolons outside of strings
+fn parse_operations(text: &str) -> Vec<(String, String)> {
+    let mut operations = Vec::new();
+    let mut curr = String::new();
+    let mut in_string = false;
+
+    for c in text.chars().chain([';']) {
+        match c {
+            '"' => {
+                in_string = !in_string;
+                curr.push(c);
+            }
+            ';' if !in_string => {
+                let operation = curr.trim();
+                if !operation.is_empty() {
+                    let (opcode, operands) = operation.split_once(' ').unwrap_or((operation, ""));
+                    let operands = operands.trim();
+                    let operands = operands
+                        .strip_prefix('"')
+                        .and_then(|o| o.strip_suffix('"'))
+                        .unwrap_or(operands);
+                    operations.push((opcode.to_string(), operands.to_string()));
+                }
+                curr.clear();
+            }
+            c => curr.push(c),
+        }
+    }
+
+    operations
+}
+
+impl FromStr for Epd {
+    type Err = &'static str;
+
+    /// the halfmove clock and fullmove number are taken from the `hmvc` and `fmvn` operations,
+    /// the position has to pass [`Game::validate`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = Vec::new();
+        let mut rest = s.trim();
+        for _ in 0..4 {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            fields.push(&rest[..end]);
+            rest = rest[end..].trim_start();
+        }
+
+        let operations = parse_operations(rest);
+        let find = |opcode: &str| {
+            operations
+                .iter()
+                .find(|(o, _)| o == opcode)
+                .map(|(_, operands)| operands.as_str())
+        };
+        let fen = format!(
+            "{} {} {}",
+            fields.join(" "),
+            find("hmvc").unwrap_or("0"),
+            find("fmvn").unwrap_or("1")
+        );
+        let game = Game::from_fen(&fen)?.validated()?;
+
+        Ok(Epd { game, operations })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operations() {
+        let epd: Epd = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - bm Bb5 Bc4; am Qe2; id \"Italian; or Spanish\"; c0 \"\";"
+            .parse()
+            .unwrap();
+        assert_eq!(epd.id(), Some("Italian; or Spanish"));
+        assert_eq!(epd.operation("bm"), Some("Bb5 Bc4"));
+        assert_eq!(epd.operation("c0"), Some(""));
+        assert_eq!(epd.operation("dm"), None);
+        assert_eq!(epd.operations().len(), 4);
+
+        let best: Vec<String> = epd
+            .moves("bm")
+            .unwrap()
+            .iter()
+            .map(Move::to_string)
+            .collect();
+        assert_eq!(best, ["f1b5", "f1c4"]);
+        assert_eq!(epd.moves("am").unwrap(), ["d1e2".parse().unwrap()]);
+        assert_eq!(epd.moves("dm"), Ok(Vec::new()));
+        assert_eq!(
+            epd.game.to_fen(),
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 1"
+        );
+    }
+
+    #[test]
+    fn move_counters() {
+        let epd: Epd = "8/8/4k3/8/8/3K4/8/8 b - - hmvc 12; fmvn 40;"
+            .parse()
+            .unwrap();
+        assert_eq!(epd.game.halfmove_clock(), 12);
+        assert_eq!(epd.game.to_fen(), "8/8/4k3/8/8/3K4/8/8 b - - 12 40");
+
+        let bare: Epd = "8/8/4k3/8/8/3K4/8/8 b - -".parse().unwrap();
+        assert!(bare.operations().is_empty());
+        assert_eq!(bare.id(), None);
+    }
+
+    #[test]
+    fn rejects_bad_moves_and_positions() {
+        let epd: Epd = "8/8/4k3/8/8/3K4/8/8 w - - bm Qd4;".parse().unwrap();
+        assert!(epd.moves("bm").is_err());
+        assert!("8/8/4k3/8/8/3K4/8 w - - bm Kd4;".parse::<Epd>().is_err());
+        assert!("".parse::<Epd>().is_err());
+    }
+}
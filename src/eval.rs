@@ -0,0 +1,454 @@
+use std::fmt::Display;
+
+use crate::{
+    figure::{Figure, FigureColor, FigureVariant, KING_STEPS},
+    game::Game,
+    square::Square,
+};
+
+// piece-square tables as seen from white's side, the first row is rank 8
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const KING_MIDDLEGAME_TABLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+#[rustfmt::skip]
+const KING_ENDGAME_TABLE: [i32; 64] = [
+    -50,-40,-30,-20,-20,-30,-40,-50,
+    -30,-20,-10,  0,  0,-10,-20,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-30,  0,  0,  0,  0,-30,-30,
+    -50,-30,-30,-30,-30,-30,-30,-50,
+];
+
+/// phase of a game with all the figures still on the board, see [`phase`]
+const OPENING_PHASE: i32 = 24;
+
+const PASSED_PAWN_BONUS: [i32; 8] = [0, 5, 10, 20, 35, 60, 100, 0];
+
+/// centipawn value of a figure
+pub fn figure_value(variant: FigureVariant) -> i32 {
+    match variant {
+        FigureVariant::Pawn => 100,
+        FigureVariant::Knight => 320,
+        FigureVariant::Bishop => 330,
+        FigureVariant::Rook => 500,
+        FigureVariant::Queen => 900,
+        FigureVariant::King => 0,
+    }
+}
+
+fn table_idx(square: Square, color: FigureColor) -> usize {
+    let file = square.file().index() as usize;
+    let row = match color {
+        FigureColor::White => 7 - square.rank().index() as usize,
+        FigureColor::Black => square.rank().index() as usize,
+    };
+    row * 8 + file
+}
+
+/// how much of the non-pawn material is left, from [`OPENING_PHASE`] down to 0 for bare kings and pawns
+fn phase(game: &Game) -> i32 {
+    let phase: i32 = game
+        .board
+        .values()
+        .map(|f| match f.variant {
+            FigureVariant::Knight | FigureVariant::Bishop => 1,
+            FigureVariant::Rook => 2,
+            FigureVariant::Queen => 4,
+            _ => 0,
+        })
+        .sum();
+    phase.min(OPENING_PHASE)
+}
+
+/// a term of the evaluation, in centipawns for each side
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Term {
+    pub white: i32,
+    pub black: i32,
+}
+
+impl Term {
+    fn add(&mut self, color: FigureColor, value: i32) {
+        match color {
+            FigureColor::White => self.white += value,
+            FigureColor::Black => self.black += value,
+        }
+    }
+
+    /// the difference from white's point of view
+    pub fn total(&self) -> i32 {
+        self.white - self.black
+    }
+}
+
+/// static evaluation of a position split into its terms, see [`Game::evaluate`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Evaluation {
+    pub material: Term,
+    pub piece_squares: Term,
+    pub mobility: Term,
+    pub king_safety: Term,
+    pub pawn_structure: Term,
+}
+
+impl Evaluation {
+    pub fn new(game: &Game) -> Self {
+        let mut eval = Evaluation::default();
+        let phase = phase(game);
+
+        for square in Square::all() {
+            let Some(figure) = game.board.get(&square) else {
+                continue;
+            };
+            let color = figure.color;
+            let idx = table_idx(square, color);
+
+            eval.material.add(color, figure_value(figure.variant));
+
+            let square_value = match figure.variant {
+                FigureVariant::Pawn => PAWN_TABLE[idx],
+                FigureVariant::Knight => KNIGHT_TABLE[idx],
+                FigureVariant::Bishop => BISHOP_TABLE[idx],
+                FigureVariant::Rook => ROOK_TABLE[idx],
+                FigureVariant::Queen => QUEEN_TABLE[idx],
+                FigureVariant::King => {
+                    (KING_MIDDLEGAME_TABLE[idx] * phase
+                        + KING_ENDGAME_TABLE[idx] * (OPENING_PHASE - phase))
+                        / OPENING_PHASE
+                }
+            };
+            eval.piece_squares.add(color, square_value);
+
+            let weight = match figure.variant {
+                FigureVariant::Knight => 4,
+                FigureVariant::Bishop => 5,
+                FigureVariant::Rook => 2,
+                FigureVariant::Queen => 1,
+                _ => 0,
+            };
+            if weight > 0 {
+                let targets = figure.targets(&square, game).len() as i32;
+                eval.mobility.add(color, weight * targets);
+            }
+        }
+
+        for color in [FigureColor::White, FigureColor::Black] {
+            eval.king_safety
+                .add(color, king_safety(game, color) * phase / OPENING_PHASE);
+            eval.pawn_structure.add(color, pawn_structure(game, color));
+        }
+
+        eval
+    }
+
+    /// the whole evaluation in centipawns from white's point of view
+    pub fn total(&self) -> i32 {
+        self.terms().iter().map(|(_, term)| term.total()).sum()
+    }
+
+    /// the whole evaluation from the point of view of `color`
+    pub fn relative(&self, color: FigureColor) -> i32 {
+        match color {
+            FigureColor::White => self.total(),
+            FigureColor::Black => -self.total(),
+        }
+    }
+
+    pub fn terms(&self) -> [(&'static str, Term); 5] {
+        [
+            ("material", self.material),
+            ("piece-square", self.piece_squares),
+            ("mobility", self.mobility),
+            ("king safety", self.king_safety),
+            ("pawn structure", self.pawn_structure),
+        ]
+    }
+}
+
+fn pawns_of(game: &Game, color: FigureColor) -> Vec<Square> {
+    let pawn = Figure::new(color, FigureVariant::Pawn);
+    Square::all()
+        .filter(|c| game.board.get(c) == Some(&pawn))
+        .collect()
+}
+
+/// pawn shield in front of the king, open files next to it and attacked fields around it
+fn king_safety(game: &Game, color: FigureColor) -> i32 {
+    let Some(king) = game.king_position(color) else {
+        return 0;
+    };
+    let own_pawns = pawns_of(game, color);
+    let dir = color.pawn_direction();
+    let mut score = 0;
+
+    for x_diff in -1..=1 {
+        let Some(file) = king.file().offset(x_diff) else {
+            continue;
+        };
+
+        let shield = |y_diff| {
+            king.offset(x_diff, y_diff)
+                .is_some_and(|c| own_pawns.contains(&c))
+        };
+        if shield(dir) {
+            score += 10;
+        } else if shield(dir * 2) {
+            score += 5;
+        }
+
+        if !own_pawns.iter().any(|p| p.file() == file) {
+            score -= 15;
+        }
+    }
+
+    for (x_diff, y_diff) in KING_STEPS {
+        if let Some(field) = king.offset(x_diff, y_diff) {
+            if game.is_attacked(field, color.opposite()) {
+                score -= 8;
+            }
+        }
+    }
+
+    score
+}
+
+/// doubled, isolated and passed pawns
+fn pawn_structure(game: &Game, color: FigureColor) -> i32 {
+    let own_pawns = pawns_of(game, color);
+    let their_pawns = pawns_of(game, color.opposite());
+    let dir = color.pawn_direction() as i32;
+    let mut score = 0;
+
+    for pawn in &own_pawns {
+        let ahead = |p: &Square| (p.rank().index() as i32 - pawn.rank().index() as i32) * dir > 0;
+
+        if own_pawns
+            .iter()
+            .any(|p| p.file() == pawn.file() && ahead(p))
+        {
+            score -= 15;
+        }
+
+        if !own_pawns
+            .iter()
+            .any(|p| p.file().distance(pawn.file()) == 1)
+        {
+            score -= 15;
+        }
+
+        let blocked = their_pawns
+            .iter()
+            .any(|p| p.file().distance(pawn.file()) <= 1 && ahead(p));
+        if !blocked {
+            let advanced = match color {
+                FigureColor::White => pawn.rank().index() as usize,
+                FigureColor::Black => 7 - pawn.rank().index() as usize,
+            };
+            score += PASSED_PAWN_BONUS[advanced];
+        }
+    }
+
+    score
+}
+
+fn format_pawns(centipawns: i32) -> String {
+    format!("{:.2}", centipawns as f64 / 100.0)
+}
+
+fn format_diff(centipawns: i32) -> String {
+    format!("{:+.2}", centipawns as f64 / 100.0)
+}
+
+impl Display for Evaluation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<16}{:>8}{:>8}{:>8}",
+            "term", "white", "black", "total"
+        )?;
+        for (name, term) in self.terms() {
+            writeln!(
+                f,
+                "{:<16}{:>8}{:>8}{:>8}",
+                name,
+                format_pawns(term.white),
+                format_pawns(term.black),
+                format_diff(term.total())
+            )?;
+        }
+        write!(f, "{:<16}{:>24}", "total", format_diff(self.total()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(fen: &str) -> Evaluation {
+        Game::from_fen(fen).unwrap().evaluate()
+    }
+
+    /// the same position with the board turned upside down and the colors swapped
+    fn mirror(fen: &str) -> String {
+        let fields: Vec<&str> = fen.split(' ').collect();
+        let swap = |s: &str| {
+            s.chars()
+                .map(|c| match c.is_ascii_uppercase() {
+                    true => c.to_ascii_lowercase(),
+                    false => c.to_ascii_uppercase(),
+                })
+                .collect::<String>()
+        };
+        let board = fields[0].split('/').rev().map(swap).collect::<Vec<_>>();
+        let side = if fields[1] == "w" { "b" } else { "w" };
+        let castling = match fields[2] {
+            "-" => "-".to_string(),
+            castling => {
+                let swapped = swap(castling);
+                let (white, black): (String, String) =
+                    swapped.chars().partition(char::is_ascii_uppercase);
+                white + &black
+            }
+        };
+        let en_passant = match fields[3].as_bytes() {
+            [file, b'3'] => format!("{}6", *file as char),
+            [file, b'6'] => format!("{}3", *file as char),
+            _ => "-".to_string(),
+        };
+        format!(
+            "{} {} {} {} {} {}",
+            board.join("/"),
+            side,
+            castling,
+            en_passant,
+            fields[4],
+            fields[5]
+        )
+    }
+
+    #[test]
+    fn material() {
+        let start = Game::new().evaluate();
+        assert_eq!(
+            start.material,
+            Term {
+                white: 4000,
+                black: 4000
+            }
+        );
+        assert_eq!(start.total(), 0);
+
+        // white is a queen up
+        let eval = evaluate("rnb1kbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3");
+        assert_eq!(eval.material.total(), 900);
+        assert!(eval.total() > 700);
+        assert_eq!(eval.relative(FigureColor::Black), -eval.total());
+
+        let bare_kings = evaluate("8/8/4k3/8/8/3K4/8/8 w - - 0 1");
+        assert_eq!(bare_kings.material, Term::default());
+        assert_eq!(bare_kings.mobility, Term::default());
+    }
+
+    #[test]
+    fn mirrored_positions_are_symmetric() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq d6 0 2",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1",
+        ] {
+            let eval = evaluate(fen);
+            let mirrored = evaluate(&mirror(fen));
+            for ((name, term), (_, mirrored)) in eval.terms().iter().zip(mirrored.terms()) {
+                assert_eq!(term.white, mirrored.black, "{} in {}", name, fen);
+                assert_eq!(term.black, mirrored.white, "{} in {}", name, fen);
+            }
+            assert_eq!(eval.total(), -mirrored.total(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn terms_sum_to_the_total() {
+        let eval = evaluate("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let sum = eval.material.total()
+            + eval.piece_squares.total()
+            + eval.mobility.total()
+            + eval.king_safety.total()
+            + eval.pawn_structure.total();
+        assert_eq!(eval.total(), sum);
+        assert_eq!(eval.terms().len(), 5);
+        assert!(eval.to_string().ends_with(&format_diff(sum)));
+    }
+}
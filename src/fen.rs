@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+
+use crate::{
+    figure::{Figure, FigureColor, FigureVariant},
+    game::{CastlingRights, Game},
+    square::{File, Rank, Square},
+};
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+fn parse_board(placement: &str) -> Result<HashMap<Square, Figure>, &'static str> {
+    let mut board = HashMap::new();
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err("the board has to have 8 ranks");
+    }
+
+    for (row, rank) in ranks.iter().zip(Rank::ALL.iter().rev()) {
+        let mut file = 0u8;
+
+        for c in row.chars() {
+            if let Some(empty) = c.to_digit(10) {
+                file = file
+                    .checked_add(empty as u8)
+                    .filter(|file| *file <= 8)
+                    .ok_or("a rank has more than 8 fields")?;
+                continue;
+            }
+            let variant = FigureVariant::from_char(c).ok_or("unknown figure letter")?;
+            let color = if c.is_ascii_uppercase() {
+                FigureColor::White
+            } else {
+                FigureColor::Black
+            };
+            let square = File::new(file)
+                .map(|file| Square::new(file, *rank))
+                .ok_or("a rank has more than 8 fields")?;
+            board.insert(square, Figure::new(color, variant));
+            file += 1;
+        }
+
+        if file != 8 {
+            return Err("a rank has to have exactly 8 fields");
+        }
+    }
+
+    Ok(board)
+}
+
+/// the rooks of `color` on its home rank on one side of its king, outermost first
+fn rooks_beside_king(
+    board: &HashMap<Square, Figure>,
+    color: FigureColor,
+    king_file: File,
+    kingside: bool,
+) -> Vec<File> {
+    let rank = color.home_rank();
+    let rook = Figure::new(color, FigureVariant::Rook);
+    let files: Vec<File> = if kingside {
+        File::ALL
+            .into_iter()
+            .rev()
+            .filter(|f| *f > king_file)
+            .collect()
+    } else {
+        File::ALL.into_iter().filter(|f| *f < king_file).collect()
+    };
+    files
+        .into_iter()
+        .filter(|f| board.get(&Square::new(*f, rank)) == Some(&rook))
+        .collect()
+}
+
+/// reads standard, X-FEN and Shredder-FEN castling fields
+pub(crate) fn parse_castling(
+    field: &str,
+    board: &HashMap<Square, Figure>,
+) -> Result<CastlingRights, &'static str> {
+    let mut rights = CastlingRights::none();
+    if field == "-" {
+        return Ok(rights);
+    }
+
+    for c in field.chars() {
+        let color = if c.is_ascii_uppercase() {
+            FigureColor::White
+        } else {
+            FigureColor::Black
+        };
+        let rank = color.home_rank();
+        let king_file = File::ALL
+            .into_iter()
+            .find(|f| {
+                board.get(&Square::new(*f, rank)) == Some(&Figure::new(color, FigureVariant::King))
+            })
+            .ok_or("castling rights need the king on its home rank")?;
+
+        let (kingside, rook_file) = match c.to_ascii_lowercase() {
+            'k' | 'q' => {
+                let kingside = c.eq_ignore_ascii_case(&'k');
+                let rook = rooks_beside_king(board, color, king_file, kingside)
+                    .first()
+                    .copied()
+                    .ok_or("castling rights need a rook to castle with")?;
+                (kingside, rook)
+            }
+            file @ 'a'..='h' => {
+                let file = File::from_char(file).ok_or("unable to parse the castling rights")?;
+                let rook = board.get(&Square::new(file, rank));
+                if rook != Some(&Figure::new(color, FigureVariant::Rook)) {
+                    return Err("castling rights need a rook to castle with");
+                }
+                (file > king_file, file)
+            }
+            _ => return Err("unable to parse the castling rights"),
+        };
+
+        rights.set(color, kingside, Some(rook_file));
+    }
+
+    Ok(rights)
+}
+
+/// whether the castling rights can only be expressed by the Chess960 rules
+pub(crate) fn needs_chess960(board: &HashMap<Square, Figure>, rights: &CastlingRights) -> bool {
+    [FigureColor::White, FigureColor::Black]
+        .iter()
+        .any(|color| {
+            let rank = color.home_rank();
+            let has_right = |kingside| rights.get(*color, kingside).is_some();
+            let king_on_e = board.get(&Square::new(File::E, rank))
+                == Some(&Figure::new(*color, FigureVariant::King));
+
+            (has_right(true) || has_right(false)) && !king_on_e
+                || rights.get(*color, true).is_some_and(|f| f != File::H)
+                || rights.get(*color, false).is_some_and(|f| f != File::A)
+        })
+}
+
+impl Game {
+    /// reads a position in Forsyth-Edwards Notation, castling rights may also be given
+    /// as X-FEN or Shredder-FEN, which makes the game follow the Chess960 castling rules,
+    /// the position is not checked with [`Game::validate`] so it can still be edited
+    pub fn from_fen(fen: &str) -> Result<Self, &'static str> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() < 4 {
+            return Err("a FEN needs at least the board, side to move, castling and en passant");
+        }
+
+        let board = parse_board(fields[0])?;
+
+        let at_turn = match fields[1] {
+            "w" => FigureColor::White,
+            "b" => FigureColor::Black,
+            _ => return Err("the side to move has to be 'w' or 'b'"),
+        };
+
+        let castling = parse_castling(fields[2], &board)?;
+
+        let en_passant = match fields[3] {
+            "-" => None,
+            field => Some(field.parse()?),
+        };
+
+        let halfmove_clock = match fields.get(4) {
+            Some(n) => n.parse().or(Err("unable to parse the halfmove clock"))?,
+            None => 0,
+        };
+        let fullmoves: u32 = match fields.get(5) {
+            Some(n) => n.parse().or(Err("unable to parse the fullmove number"))?,
+            None => 1,
+        };
+        let turns = (fullmoves.max(1) - 1)
+            .checked_mul(2)
+            .and_then(|turns| turns.checked_add((at_turn == FigureColor::Black) as u32))
+            .ok_or("the fullmove number is too large")?;
+
+        // rook files as castling rights only come up in Chess960
+        let files = fields[2]
+            .chars()
+            .any(|c| !matches!(c, 'K' | 'Q' | 'k' | 'q' | '-'));
+        let chess960 = files || needs_chess960(&board, &castling);
+
+        Ok(Self::from_parts(
+            board,
+            at_turn,
+            castling,
+            en_passant,
+            halfmove_clock,
+            turns,
+            chess960,
+        ))
+    }
+
+    /// reads a position of a Chess960 game, castling follows the Chess960 rules even
+    /// when the king and rooks stand where they do in standard chess
+    pub fn from_fen_chess960(fen: &str) -> Result<Self, &'static str> {
+        let mut game = Self::from_fen(fen)?;
+        game.chess960 = true;
+        Ok(game)
+    }
+
+    /// the position in FEN, using X-FEN castling rights when a rook other than the
+    /// outermost one may castle, so standard games get plain FEN, Chess960 games that
+    /// look like standard chess get Shredder-FEN so that they read back as Chess960
+    pub fn to_fen(&self) -> String {
+        self.fen_with_castling(false)
+    }
+
+    /// the position in Shredder-FEN, castling rights are always written as rook files
+    pub fn to_shredder_fen(&self) -> String {
+        self.fen_with_castling(true)
+    }
+
+    fn fen_with_castling(&self, shredder: bool) -> String {
+        let mut placement = String::new();
+        for rank in Rank::ALL.into_iter().rev() {
+            let mut empty = 0;
+            for file in File::ALL {
+                match self.board.get(&Square::new(file, rank)) {
+                    Some(figure) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        placement.push(figure.get_letter());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if rank != Rank::FIRST {
+                placement.push('/');
+            }
+        }
+
+        let side = match self.side_to_move() {
+            FigureColor::White => 'w',
+            FigureColor::Black => 'b',
+        };
+
+        let mut castling = String::new();
+        let rights = self.castling_rights();
+        let shredder = shredder || self.chess960 && !needs_chess960(&self.board, &rights);
+        for color in [FigureColor::White, FigureColor::Black] {
+            for kingside in [true, false] {
+                let Some(rook_file) = rights.get(color, kingside) else {
+                    continue;
+                };
+                let outermost = self.king_position(color).and_then(|king| {
+                    rooks_beside_king(&self.board, color, king.file(), kingside)
+                        .first()
+                        .copied()
+                });
+                let c = match (shredder, outermost == Some(rook_file), kingside) {
+                    (false, true, true) => 'k',
+                    (false, true, false) => 'q',
+                    _ => rook_file.to_char(),
+                };
+                castling.push(match color {
+                    FigureColor::White => c.to_ascii_uppercase(),
+                    FigureColor::Black => c,
+                });
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant = self
+            .en_passant()
+            .map_or("-".to_string(), |square| square.to_string());
+
+        format!(
+            "{} {} {} {} {} {}",
+            placement,
+            side,
+            castling,
+            en_passant,
+            self.halfmove_clock(),
+            self.turns() / 2 + 1
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        for fen in [
+            STARTING_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 11 42",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9",
+        ] {
+            assert_eq!(Game::from_fen(fen).unwrap().to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn shredder_castling() {
+        let game = Game::from_fen(STARTING_FEN).unwrap();
+        assert!(game.to_shredder_fen().contains(" HAha "));
+        assert!(!game.is_chess960());
+        let shredder = STARTING_FEN.replace("KQkq", "HAha");
+        let game = Game::from_fen(&shredder).unwrap();
+        assert!(game.is_chess960());
+        assert_eq!(game.to_fen(), shredder);
+    }
+
+    #[test]
+    fn chess960_looking_like_standard_chess() {
+        let game = Game::new_chess960(518).unwrap();
+        let read = Game::from_fen(&game.to_fen()).unwrap();
+        assert!(read.is_chess960());
+        assert_eq!(read.to_fen(), game.to_fen());
+        assert_eq!(read.legal_moves().len(), 20);
+
+        // once the rights are gone nothing tells the rules apart anymore
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1";
+        assert_eq!(Game::from_fen_chess960(fen).unwrap().to_fen(), fen);
+    }
+
+    #[test]
+    fn fullmove_number() {
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+        assert_eq!(Game::from_fen(fen).unwrap().turns(), 1);
+        let fen = STARTING_FEN.replace(" 0 1", " 0 0");
+        assert_eq!(Game::from_fen(&fen).unwrap().turns(), 0);
+    }
+
+    #[test]
+    fn bad_fens() {
+        for fen in [
+            "",
+            "8/8/8/8 w - -",
+            "99999999999999999999999999999/8/8/8/8/8/8/8 w - -",
+            "9/8/8/8/8/8/8/8 w - -",
+            "ppppppppp/8/8/8/8/8/8/8 w - -",
+            "7/8/8/8/8/8/8/8 w - -",
+            "x7/8/8/8/8/8/8/8 w - -",
+            "4k3/8/8/8/8/8/8/4K3 x - -",
+            "4k3/8/8/8/8/8/8/4K3 w KQ -",
+            "4k3/8/8/8/8/8/8/4K3 w - z9",
+            "4k3/8/8/8/8/8/8/4K3 w - - -1 1",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 4294967295",
+            "4k3/8/8/8/8/8/8/4K3 b - - 0 4294967295",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 99999999999",
+        ] {
+            assert!(Game::from_fen(fen).is_err(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn huge_clocks_still_play() {
+        let fen = "4k3/8/8/8/8/8/8/4K3 w - - 4294967295 2147483648";
+        let mut game = Game::from_fen(fen).unwrap();
+        game.apply_move("e1e2".parse().unwrap()).unwrap();
+        game.apply_move("e8e7".parse().unwrap()).unwrap();
+        assert_eq!(game.halfmove_clock(), u32::MAX);
+    }
+}
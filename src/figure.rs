@@ -1,7 +1,33 @@
 use colored::Colorize;
 use std::fmt::Display;
 
-use crate::game::{Coords, Game};
+use crate::{
+    game::Game,
+    square::{Rank, Square},
+};
+
+pub(crate) const ROOK_DIRECTIONS: [(i8, i8); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+pub(crate) const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+pub(crate) const KNIGHT_JUMPS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+pub(crate) const KING_STEPS: [(i8, i8); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
 
 impl Display for FigureColor {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -20,19 +46,73 @@ impl Display for Figure {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FigureColor {
     White,
     Black,
 }
 
-#[derive(Debug)]
+impl FigureColor {
+    /// the plain name of the color, `Display` dresses it up in colors for the terminal
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::White => "white",
+            Self::Black => "black",
+        }
+    }
+
+    /// white 0 and black 1, for arrays holding something of each side
+    pub fn index(&self) -> usize {
+        match self {
+            Self::White => 0,
+            Self::Black => 1,
+        }
+    }
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::White => Self::Black,
+            Self::Black => Self::White,
+        }
+    }
+
+    /// which way the pawns of this color walk along the ranks
+    pub(crate) fn pawn_direction(&self) -> i8 {
+        match self {
+            Self::White => 1,
+            Self::Black => -1,
+        }
+    }
+
+    pub(crate) fn home_rank(&self) -> Rank {
+        match self {
+            Self::White => Rank::FIRST,
+            Self::Black => Rank::EIGHTH,
+        }
+    }
+
+    /// the rank the pawns of this color start on
+    pub(crate) fn pawn_rank(&self) -> Rank {
+        self.home_rank()
+            .offset(self.pawn_direction())
+            .expect("the home rank is at the edge of the board")
+    }
+
+    pub(crate) fn promotion_rank(&self) -> Rank {
+        self.opposite().home_rank()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Figure {
     pub color: FigureColor,
-    variant: FigureVariant,
+    pub variant: FigureVariant,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FigureVariant {
     Rook,
     Pawn,
@@ -42,8 +122,62 @@ pub enum FigureVariant {
     King,
 }
 
+impl FigureVariant {
+    /// the figures a pawn can be promoted to, strongest first
+    pub const PROMOTIONS: [FigureVariant; 4] = [
+        FigureVariant::Queen,
+        FigureVariant::Rook,
+        FigureVariant::Bishop,
+        FigureVariant::Knight,
+    ];
+
+    /// parses the usual english letter of a figure, eg `n` for knight, in either case
+    pub fn from_char(c: char) -> Option<Self> {
+        match c.to_ascii_lowercase() {
+            'p' => Some(Self::Pawn),
+            'r' => Some(Self::Rook),
+            'n' => Some(Self::Knight),
+            'b' => Some(Self::Bishop),
+            'q' => Some(Self::Queen),
+            'k' => Some(Self::King),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(&self) -> char {
+        match self {
+            Self::Pawn => 'p',
+            Self::Rook => 'r',
+            Self::Knight => 'n',
+            Self::Bishop => 'b',
+            Self::Queen => 'q',
+            Self::King => 'k',
+        }
+    }
+
+    fn is_slider(&self) -> bool {
+        matches!(self, Self::Rook | Self::Bishop | Self::Queen)
+    }
+
+    fn directions(&self) -> &'static [(i8, i8)] {
+        match self {
+            Self::Rook => &ROOK_DIRECTIONS,
+            Self::Bishop => &BISHOP_DIRECTIONS,
+            Self::Queen | Self::King => &KING_STEPS,
+            Self::Knight => &KNIGHT_JUMPS,
+            Self::Pawn => &[],
+        }
+    }
+}
+
 impl Figure {
-    pub fn can_go(&self, field: &Coords, game: &Game, target: &Coords) -> Result<(), &'static str> {
+    pub fn new(color: FigureColor, variant: FigureVariant) -> Self {
+        Figure { color, variant }
+    }
+
+    /// checks whether the figure is able to get from `field` to `target`, not minding
+    /// whether that would leave its king in check, castling is handled by [`Game`]
+    pub fn can_go(&self, field: &Square, game: &Game, target: &Square) -> Result<(), &'static str> {
         if field == target {
             return Err("select different field than the current one");
         }
@@ -54,73 +188,141 @@ impl Figure {
             }
         }
 
+        let x_diff = target.file().index() as i8 - field.file().index() as i8;
+        let y_diff = target.rank().index() as i8 - field.rank().index() as i8;
+
         match self.variant {
             FigureVariant::Pawn => {
-                let y_diff: i8 = match self.color {
-                    FigureColor::Black => -1,
-                    FigureColor::White => 1,
-                };
-
-                let allow_double = matches!(
-                    (&self.color, field.1),
-                    (FigureColor::Black, 7) | (FigureColor::White, 2)
-                );
-
-                let mut goes_straight =
-                    field.0 == target.0 && ((target.1 as i8 - field.1 as i8) == y_diff);
-
-                if allow_double {
-                    goes_straight |=
-                        field.0 == target.0 && ((target.1 as i8 - field.1 as i8) == y_diff * 2);
-                }
-
-                // TODO this needs to check if the 'across' field is occupied by an opponent
-                let takes_across = (field.0 as i8).abs_diff(target.0 as i8) == 1
-                    && (target.1 as i8 - field.1 as i8) == y_diff;
+                let dir = self.color.pawn_direction();
+                let start_rank = self.color.pawn_rank();
 
-                if goes_straight || takes_across {
+                if x_diff == 0
+                    && (y_diff == dir || (y_diff == dir * 2 && field.rank() == start_rank))
+                {
+                    Self::path_clear(field, target, game)?;
+                    if game.board.contains_key(target) {
+                        return Err("something is in the way");
+                    }
                     Ok(())
+                } else if x_diff.abs() == 1 && y_diff == dir {
+                    if game.board.contains_key(target) || game.en_passant() == Some(*target) {
+                        Ok(())
+                    } else {
+                        Err("the pawn can only go across when taking")
+                    }
                 } else {
                     Err("the figure cannot go there")
                 }
             }
             FigureVariant::Rook => {
-                if target.1 == field.1 && target.0 != field.0 {
-                    let range = if field.0 > target.0 {
-                        target.0 as u32..field.0 as u32 - 1
-                    } else {
-                        field.0 as u32 + 1..target.0 as u32
-                    };
-                    for i in range {
-                        if game
-                            .board
-                            .get(&(char::from_u32('a' as u32 + i).unwrap(), field.1))
-                            .is_some()
-                        {
-                            return Err("something is in the way");
-                        }
-                    }
+                if (x_diff == 0) != (y_diff == 0) {
+                    Self::path_clear(field, target, game)
+                } else {
+                    Err("the figure cannot go there")
+                }
+            }
+            FigureVariant::Bishop => {
+                if x_diff.abs() == y_diff.abs() {
+                    Self::path_clear(field, target, game)
+                } else {
+                    Err("the figure cannot go there")
+                }
+            }
+            FigureVariant::Queen => {
+                if x_diff == 0 || y_diff == 0 || x_diff.abs() == y_diff.abs() {
+                    Self::path_clear(field, target, game)
+                } else {
+                    Err("the figure cannot go there")
+                }
+            }
+            FigureVariant::Knight => {
+                if matches!((x_diff.abs(), y_diff.abs()), (1, 2) | (2, 1)) {
                     Ok(())
-                } else if target.1 != field.1 && target.0 == field.0 {
-                    let range = if field.1 > target.1 {
-                        target.1..field.1 - 1
-                    } else {
-                        field.1 + 1..target.1
-                    };
-                    for i in range {
-                        if game.board.get(&(field.0, i)).is_some() {
-                            return Err("something is in the way");
-                        }
-                    }
+                } else {
+                    Err("the figure cannot go there")
+                }
+            }
+            FigureVariant::King => {
+                if x_diff.abs() <= 1 && y_diff.abs() <= 1 {
                     Ok(())
                 } else {
                     Err("the figure cannot go there")
                 }
             }
-            _ => unimplemented!(),
         }
     }
-    fn get_symbol(&self) -> char {
+
+    /// all the fields the figure can go to from `field`, again not minding checks or castling
+    pub fn targets(&self, field: &Square, game: &Game) -> Vec<Square> {
+        let mut targets = Vec::new();
+
+        if self.variant == FigureVariant::Pawn {
+            let dir = self.color.pawn_direction();
+
+            if let Some(one) = field.offset(0, dir).filter(|c| !game.board.contains_key(c)) {
+                targets.push(one);
+                if field.rank() == self.color.pawn_rank() {
+                    if let Some(two) = field
+                        .offset(0, dir * 2)
+                        .filter(|c| !game.board.contains_key(c))
+                    {
+                        targets.push(two);
+                    }
+                }
+            }
+
+            for x_diff in [-1, 1] {
+                if let Some(across) = field.offset(x_diff, dir) {
+                    let takes = game
+                        .board
+                        .get(&across)
+                        .is_some_and(|f| f.color != self.color);
+                    if takes || game.en_passant() == Some(across) {
+                        targets.push(across);
+                    }
+                }
+            }
+
+            return targets;
+        }
+
+        for (x_diff, y_diff) in self.variant.directions() {
+            let mut curr = *field;
+            while let Some(next) = curr.offset(*x_diff, *y_diff) {
+                match game.board.get(&next) {
+                    Some(other) => {
+                        if other.color != self.color {
+                            targets.push(next);
+                        }
+                        break;
+                    }
+                    None => targets.push(next),
+                }
+                if !self.variant.is_slider() {
+                    break;
+                }
+                curr = next;
+            }
+        }
+
+        targets
+    }
+
+    fn path_clear(field: &Square, target: &Square, game: &Game) -> Result<(), &'static str> {
+        let x_step = (target.file().index() as i8 - field.file().index() as i8).signum();
+        let y_step = (target.rank().index() as i8 - field.rank().index() as i8).signum();
+
+        let mut curr = field.offset(x_step, y_step);
+        while let Some(square) = curr.filter(|c| c != target) {
+            if game.board.contains_key(&square) {
+                return Err("something is in the way");
+            }
+            curr = square.offset(x_step, y_step);
+        }
+        Ok(())
+    }
+
+    pub fn get_symbol(&self) -> char {
         match (&self.color, &self.variant) {
             (FigureColor::White, FigureVariant::Rook) => '♖',
             (FigureColor::Black, FigureVariant::Rook) => '♜',
@@ -137,35 +339,37 @@ impl Figure {
         }
     }
 
-    pub fn from_str(code: &str) -> Option<Self> {
-        let color_code = code.chars().nth(0).unwrap();
-        let variant_code = code.chars().nth(1).unwrap();
+    /// the letter used for the figure in ascii drawings, uppercase for white
+    pub fn get_letter(&self) -> char {
+        let letter = self.variant.to_char();
+        match self.color {
+            FigureColor::White => letter.to_ascii_uppercase(),
+            FigureColor::Black => letter,
+        }
+    }
 
-        let color = match color_code {
-            'b' => Some(FigureColor::Black),
-            'w' => Some(FigureColor::White),
-            ' ' => None,
-            _ => panic!(),
+    /// reads the two-letter codes of the built-in setups, eg `wK` or `bp`, `None` for
+    /// empty fields and anything else
+    pub(crate) fn from_code(code: &str) -> Option<Self> {
+        let mut chars = code.chars();
+        let color = match chars.next()? {
+            'b' => FigureColor::Black,
+            'w' => FigureColor::White,
+            _ => return None,
         };
-
-        let variant = match variant_code {
-            'p' => Some(FigureVariant::Pawn),
-            'r' => Some(FigureVariant::Rook),
-            'k' => Some(FigureVariant::Knight),
-            'b' => Some(FigureVariant::Bishop),
-            'K' => Some(FigureVariant::King),
-            'q' => Some(FigureVariant::Queen),
-            ' ' => None,
-            _ => panic!(),
+        let variant = match chars.next()? {
+            'p' => FigureVariant::Pawn,
+            'r' => FigureVariant::Rook,
+            'k' => FigureVariant::Knight,
+            'b' => FigureVariant::Bishop,
+            'K' => FigureVariant::King,
+            'q' => FigureVariant::Queen,
+            _ => return None,
         };
-
-        if color.is_none() || variant.is_none() {
-            None
-        } else {
-            Some(Figure {
-                variant: variant?,
-                color: color?,
-            })
+        if chars.next().is_some() {
+            return None;
         }
+
+        Some(Figure { variant, color })
     }
 }
@@ -1,54 +1,127 @@
-use std::{collections::HashMap, fmt::Display, io::stdin};
+use std::{collections::HashMap, fmt::Display};
 
-use colored::{Colorize, CustomColor};
-
-use crate::figure::{Figure, FigureColor};
+use crate::{
+    eval::{figure_value, Evaluation},
+    figure::{
+        Figure, FigureColor, FigureVariant, BISHOP_DIRECTIONS, KING_STEPS, KNIGHT_JUMPS,
+        ROOK_DIRECTIONS,
+    },
+    moves::Move,
+    render::{BoardView, RenderOptions},
+    square::{File, Rank, Square},
+};
 
 pub type BoardSetup = &'static [&'static str; 8];
-pub type Coords = (char, u8);
 
-fn get_bg_color(coords: Coords) -> CustomColor {
-    let light_bg = CustomColor::new(204, 183, 174);
-    let dark_bg = CustomColor::new(112, 102, 119);
-    match coords {
-        (y, x) if x % 2 == y as u8 % 2 => light_bg,
-        _ => dark_bg,
+/// `Check`, `Checkmate` and `Stalemate` hold the color of the side to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameState {
+    Check(FigureColor),
+    Checkmate(FigureColor),
+    Stalemate(FigureColor),
+    /// the color that ran out of time, see [`Game::lose_on_time`]
+    OutOfTime(FigureColor),
+    Play,
+}
+
+/// files of the rooks that may still castle, `None` once the right is lost
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CastlingRights {
+    pub white_kingside: Option<File>,
+    pub white_queenside: Option<File>,
+    pub black_kingside: Option<File>,
+    pub black_queenside: Option<File>,
+}
+
+impl CastlingRights {
+    /// both sides may castle with the rooks in the corners
+    pub fn standard() -> Self {
+        CastlingRights {
+            white_kingside: Some(File::H),
+            white_queenside: Some(File::A),
+            black_kingside: Some(File::H),
+            black_queenside: Some(File::A),
+        }
+    }
+
+    pub fn none() -> Self {
+        CastlingRights {
+            white_kingside: None,
+            white_queenside: None,
+            black_kingside: None,
+            black_queenside: None,
+        }
+    }
+
+    pub fn get(&self, color: FigureColor, kingside: bool) -> Option<File> {
+        match (color, kingside) {
+            (FigureColor::White, true) => self.white_kingside,
+            (FigureColor::White, false) => self.white_queenside,
+            (FigureColor::Black, true) => self.black_kingside,
+            (FigureColor::Black, false) => self.black_queenside,
+        }
+    }
+
+    pub fn set(&mut self, color: FigureColor, kingside: bool, rook_file: Option<File>) {
+        match (color, kingside) {
+            (FigureColor::White, true) => self.white_kingside = rook_file,
+            (FigureColor::White, false) => self.white_queenside = rook_file,
+            (FigureColor::Black, true) => self.black_kingside = rook_file,
+            (FigureColor::Black, false) => self.black_queenside = rook_file,
+        }
+    }
+
+    fn revoke(&mut self, color: FigureColor, kingside: bool) {
+        self.set(color, kingside, None);
     }
 }
 
-fn read_algebraic() -> Result<(Coords, Coords), &'static str> {
-    let i = stdin();
-    let mut buf = String::new();
-    i.read_line(&mut buf).or(Err("unable to read input"))?;
-    let from_x: char = buf[0..1]
-        .parse()
-        .or(Err("unable to parse 'from_x', it has to be a letter"))?;
-    let from_y: u8 = buf[1..2]
-        .parse()
-        .or(Err("unable to parse 'from_y', it has to be a number"))?;
-    let to_x: char = buf[2..3]
-        .parse()
-        .or(Err("unable to parse 'to_x', it has to be a letter"))?;
-    let to_y: u8 = buf[3..4]
-        .parse()
-        .or(Err("unable to parse 'to_y', it has to be a number"))?;
-    Ok(((from_x, from_y), (to_x, to_y)))
+/// what a move does on the board besides moving a single figure
+enum Special {
+    None,
+    EnPassant(Square),
+    Castling {
+        rook_from: Square,
+        rook_to: Square,
+        king_to: Square,
+    },
 }
 
-#[allow(dead_code)]
-enum GameState {
-    Check(FigureColor),
-    Checkmate(FigureColor),
-    Stalemate(FigureColor),
-    Play,
+/// where king and rook end up after castling, (king, rook)
+fn castled_fields(color: FigureColor, kingside: bool) -> (Square, Square) {
+    let rank = color.home_rank();
+    if kingside {
+        (Square::new(File::G, rank), Square::new(File::F, rank))
+    } else {
+        (Square::new(File::C, rank), Square::new(File::D, rank))
+    }
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        into = "crate::record::GameRecord",
+        try_from = "crate::record::GameRecord"
+    )
+)]
 pub struct Game {
-    pub board: HashMap<Coords, Figure>,
+    pub board: HashMap<Square, Figure>,
     at_turn: FigureColor,
-    #[allow(dead_code)]
     turns: u32,
     state: GameState,
+    castling: CastlingRights,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    history: Vec<Move>,
+    start_fen: String,
+    pub(crate) chess960: bool,
+    /// the figures taken so far, see [`Game::captured`], kept up to date by every move
+    /// so drawing the game does not replay it
+    captured: Vec<Figure>,
 }
 
 impl Game {
@@ -67,117 +140,587 @@ impl Game {
     }
 
     fn from_str_arr(setup: BoardSetup) -> Self {
-        let mut board: HashMap<Coords, Figure> = HashMap::new();
+        let mut board: HashMap<Square, Figure> = HashMap::new();
 
-        for (rank_idx, rank) in setup.iter().enumerate() {
-            let mut curr = &rank[0..];
-            let mut file_cnt = 0;
+        for (row, rank) in setup.iter().zip(Rank::ALL.iter().rev()) {
+            let codes = row.as_bytes().chunks(2);
 
-            while curr.len() >= 2 {
-                let fig = Figure::from_str(&curr[..2]);
+            for (code, file) in codes.zip(File::ALL) {
+                let fig = std::str::from_utf8(code).ok().and_then(Figure::from_code);
 
                 if let Some(fig) = fig {
-                    let letter = char::from_u32('a' as u32 + file_cnt).unwrap();
-                    let number = 8 - rank_idx as u8;
-                    board.insert((letter, number), fig);
+                    board.insert(Square::new(file, *rank), fig);
                 }
-
-                file_cnt += 1;
-                curr = &curr[2..];
             }
         }
 
-        Game {
+        Self::from_parts(
+            board,
+            FigureColor::White,
+            CastlingRights::standard(),
+            None,
+            0,
+            0,
+            false,
+        )
+    }
+
+    pub(crate) fn from_parts(
+        board: HashMap<Square, Figure>,
+        at_turn: FigureColor,
+        castling: CastlingRights,
+        en_passant: Option<Square>,
+        halfmove_clock: u32,
+        turns: u32,
+        chess960: bool,
+    ) -> Self {
+        let mut game = Game {
             board,
-            at_turn: FigureColor::White,
-            turns: 0,
+            at_turn,
+            turns,
             state: GameState::Play,
+            castling,
+            en_passant,
+            halfmove_clock,
+            history: Vec::new(),
+            start_fen: String::new(),
+            chess960,
+            captured: Vec::new(),
+        };
+        game.state = game.compute_state();
+        game.start_fen = game.to_fen();
+        game
+    }
+
+    pub fn side_to_move(&self) -> FigureColor {
+        self.at_turn
+    }
+
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    /// ends the game with a loss for `color`, whose clock ran out, a game that is
+    /// already over keeps its result
+    pub fn lose_on_time(&mut self, color: FigureColor) {
+        if !self.is_over() {
+            self.state = GameState::OutOfTime(color);
         }
     }
 
-    fn turn(&mut self) -> Result<(), &str> {
-        println!("{}", "----------------------------".cyan());
-        println!("{}'s turn", self.at_turn);
-        println!("{}", self);
-        println!("input '{{from}}{{to}}', eg 'a1a2': ");
-        let ((from_x, from_y), (to_x, to_y)) = read_algebraic()?;
+    pub fn is_over(&self) -> bool {
+        !matches!(self.state, GameState::Play | GameState::Check(_))
+    }
+
+    /// number of half-moves played so far
+    pub fn turns(&self) -> u32 {
+        self.turns
+    }
+
+    /// whether castling follows the Chess960 rules, where castling moves are written
+    /// as the king taking its own rook
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
 
-        let selected_figure = self
+    pub fn castling_rights(&self) -> CastlingRights {
+        self.castling
+    }
+
+    /// the field a pawn skipped over with a double step in the last move
+    pub fn en_passant(&self) -> Option<Square> {
+        self.en_passant
+    }
+
+    /// half-moves since the last capture or pawn move
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    /// the position the game started from, the moves of [`Game::history`] were played from it
+    pub fn start_fen(&self) -> &str {
+        &self.start_fen
+    }
+
+    pub fn last_move(&self) -> Option<Move> {
+        self.history.last().copied()
+    }
+
+    /// the figures white and black took from the opponent so far, most valuable first,
+    /// in the order of [`FigureColor::index`]
+    pub fn captured(&self) -> [Vec<Figure>; 2] {
+        let mut captured = [Vec::new(), Vec::new()];
+        for figure in &self.captured {
+            captured[figure.color.opposite().index()].push(*figure);
+        }
+        for taken in &mut captured {
+            taken.sort_by_key(|f| std::cmp::Reverse(figure_value(f.variant)));
+        }
+        captured
+    }
+
+    /// draws the board like `Display` does, but with the given orientation, theme and highlights
+    pub fn render<'a>(&'a self, options: &'a RenderOptions) -> BoardView<'a> {
+        BoardView::new(self, options)
+    }
+
+    /// static evaluation of the position, split into its terms
+    pub fn evaluate(&self) -> Evaluation {
+        Evaluation::new(self)
+    }
+
+    pub fn king_position(&self, color: FigureColor) -> Option<Square> {
+        Square::all().find(|c| self.board.get(c) == Some(&Figure::new(color, FigureVariant::King)))
+    }
+
+    pub fn is_in_check(&self, color: FigureColor) -> bool {
+        self.king_position(color)
+            .is_some_and(|king| is_attacked(&self.board, king, color.opposite()))
+    }
+
+    /// whether any figure of color `by` attacks the `square` field
+    pub fn is_attacked(&self, square: Square, by: FigureColor) -> bool {
+        is_attacked(&self.board, square, by)
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+
+        for field in Square::all() {
+            let Some(figure) = self.board.get(&field) else {
+                continue;
+            };
+            if figure.color != self.at_turn {
+                continue;
+            }
+
+            for target in figure.targets(&field, self) {
+                if figure.variant == FigureVariant::Pawn
+                    && target.rank() == figure.color.promotion_rank()
+                {
+                    for variant in FigureVariant::PROMOTIONS {
+                        moves.push(Move::with_promotion(field, target, variant));
+                    }
+                } else {
+                    moves.push(Move::new(field, target));
+                }
+            }
+        }
+
+        moves.extend(self.castling_moves());
+        moves.retain(|m| self.keeps_king_safe(m));
+        moves
+    }
+
+    /// plays the move for the side at turn, a pawn reaching the last rank without
+    /// a chosen promotion becomes a queen
+    pub fn apply_move(&mut self, m: Move) -> Result<(), &'static str> {
+        if self.is_over() {
+            return Err("the game is already over");
+        }
+
+        let figure = *self
             .board
-            .remove(&(from_x, from_y))
+            .get(&m.from)
             .ok_or("theres no figure on that field")?;
 
-        if selected_figure.color != self.at_turn {
-            self.board.insert((from_x, from_y), selected_figure);
+        if figure.color != self.at_turn {
             return Err("cannot move opponent's figure");
         }
 
-        match selected_figure.can_go(&(from_x, from_y), self, &(to_x, to_y)) {
-            Ok(_) => {
-                self.board.insert((to_x, to_y), selected_figure);
-                Ok(())
+        let mut m = self
+            .castling_side(&m)
+            .and_then(|kingside| self.castling_move(kingside))
+            .unwrap_or(m);
+        if figure.variant == FigureVariant::Pawn
+            && m.to.rank() == figure.color.promotion_rank()
+            && m.promotion.is_none()
+        {
+            m.promotion = Some(FigureVariant::Queen);
+        }
+
+        if !self.legal_moves().contains(&m) {
+            if m.promotion.is_some() && figure.variant != FigureVariant::Pawn {
+                return Err("only pawns can be promoted");
             }
-            Err(e) => {
-                self.board.insert((from_x, from_y), selected_figure);
-                Err(e)
+            if self.castling_side(&m).is_some() {
+                return Err("castling is not possible now");
             }
-        }?;
+            figure.can_go(&m.from, self, &m.to)?;
+            return Err("your king would be in check");
+        }
 
-        self.switch_sides();
+        self.play(m);
         Ok(())
     }
 
+    /// plays a move taken from [`Game::legal_moves`] without checking it again
+    pub(crate) fn play(&mut self, m: Move) {
+        let figure = self.board[&m.from];
+        let special = self.special(&m);
+        // a Chess960 king castles by taking its own rook
+        let taken = match special {
+            Special::EnPassant(_) => {
+                Some(Figure::new(figure.color.opposite(), FigureVariant::Pawn))
+            }
+            _ => self
+                .board
+                .get(&m.to)
+                .filter(|f| f.color != figure.color)
+                .copied(),
+        };
+        let takes = taken.is_some();
+        if let Some(taken) = taken {
+            self.captured.push(taken);
+        }
+
+        if figure.variant == FigureVariant::Pawn || takes {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+        }
+
+        if figure.variant == FigureVariant::King {
+            self.castling.revoke(figure.color, true);
+            self.castling.revoke(figure.color, false);
+        }
+        for color in [FigureColor::White, FigureColor::Black] {
+            for kingside in [true, false] {
+                if let Some(file) = self.castling.get(color, kingside) {
+                    let rook = Square::new(file, color.home_rank());
+                    if m.from == rook || m.to == rook {
+                        self.castling.revoke(color, kingside);
+                    }
+                }
+            }
+        }
+
+        self.en_passant = None;
+        if figure.variant == FigureVariant::Pawn && m.to.rank().distance(m.from.rank()) == 2 {
+            self.en_passant = m.from.offset(0, figure.color.pawn_direction());
+        }
+
+        move_figures(&mut self.board, &m, special);
+
+        self.history.push(m);
+        self.turns = self.turns.saturating_add(1);
+        self.switch_sides();
+        self.state = self.compute_state();
+    }
+
     fn switch_sides(&mut self) {
-        if self.at_turn == FigureColor::Black {
-            self.at_turn = FigureColor::White
+        self.at_turn = self.at_turn.opposite();
+    }
+
+    fn compute_state(&self) -> GameState {
+        let in_check = self.is_in_check(self.at_turn);
+        let can_move = !self.legal_moves().is_empty();
+
+        match (in_check, can_move) {
+            (true, true) => GameState::Check(self.at_turn),
+            (true, false) => GameState::Checkmate(self.at_turn),
+            (false, false) => GameState::Stalemate(self.at_turn),
+            (false, true) => GameState::Play,
+        }
+    }
+
+    fn keeps_king_safe(&self, m: &Move) -> bool {
+        let mut board = self.board.clone();
+        move_figures(&mut board, m, self.special(m));
+
+        let king = Figure::new(self.at_turn, FigureVariant::King);
+        match board.iter().find(|(_, f)| **f == king) {
+            Some((square, _)) => !is_attacked(&board, *square, self.at_turn.opposite()),
+            None => true,
+        }
+    }
+
+    fn special(&self, m: &Move) -> Special {
+        let Some(figure) = self.board.get(&m.from) else {
+            return Special::None;
+        };
+
+        if let Some(kingside) = self.castling_side(m) {
+            if let Some(rook_file) = self.castling.get(figure.color, kingside) {
+                let (king_to, rook_to) = castled_fields(figure.color, kingside);
+                return Special::Castling {
+                    rook_from: Square::new(rook_file, m.from.rank()),
+                    rook_to,
+                    king_to,
+                };
+            }
+        }
+
+        if figure.variant == FigureVariant::Pawn
+            && Some(m.to) == self.en_passant
+            && m.to.file() != m.from.file()
+        {
+            return Special::EnPassant(Square::new(m.to.file(), m.from.rank()));
+        }
+
+        Special::None
+    }
+
+    /// `Some(kingside)` when the move is the king castling, either taking its own rook
+    /// or, outside of Chess960, going two fields towards it
+    pub(crate) fn castling_side(&self, m: &Move) -> Option<bool> {
+        let king = self.board.get(&m.from)?;
+        if king.variant != FigureVariant::King
+            || m.from.rank() != king.color.home_rank()
+            || m.to.rank() != m.from.rank()
+        {
+            return None;
+        }
+
+        if self.board.get(&m.to) == Some(&Figure::new(king.color, FigureVariant::Rook)) {
+            return [true, false]
+                .into_iter()
+                .find(|kingside| self.castling.get(king.color, *kingside) == Some(m.to.file()));
+        }
+
+        if !self.chess960 && m.from.file() == File::E && matches!(m.to.file(), File::G | File::C) {
+            return Some(m.to.file() == File::G);
+        }
+
+        None
+    }
+
+    /// the castling move of the side at turn the way [`Game::legal_moves`] writes it
+    fn castling_move(&self, kingside: bool) -> Option<Move> {
+        let color = self.at_turn;
+        let rook_file = self.castling.get(color, kingside)?;
+        let king = self
+            .king_position(color)
+            .filter(|k| k.rank() == color.home_rank())?;
+
+        if self.chess960 {
+            Some(Move::new(king, Square::new(rook_file, king.rank())))
         } else {
-            self.at_turn = FigureColor::Black
+            Some(Move::new(king, castled_fields(color, kingside).0))
         }
     }
 
-    pub fn game_loop(&mut self) {
-        while matches!(self.state, GameState::Play | GameState::Check(_)) {
-            match self.turn() {
-                Ok(_) => (),
-                Err(e) => println!("{}", format!("err: {}", e).red()),
+    fn castling_moves(&self) -> Vec<Move> {
+        let color = self.at_turn;
+        let mut moves = Vec::new();
+
+        let Some(king) = self.king_position(color) else {
+            return moves;
+        };
+        if king.rank() != color.home_rank() || is_attacked(&self.board, king, color.opposite()) {
+            return moves;
+        }
+
+        for kingside in [true, false] {
+            let Some(rook_file) = self.castling.get(color, kingside) else {
+                continue;
             };
+            let rook = Square::new(rook_file, king.rank());
+            if self.board.get(&rook) != Some(&Figure::new(color, FigureVariant::Rook)) {
+                continue;
+            }
+
+            let (king_to, rook_to) = castled_fields(color, kingside);
+            let files = [king.file(), rook.file(), king_to.file(), rook_to.file()];
+            let (lowest, highest) = (*files.iter().min().unwrap(), *files.iter().max().unwrap());
+
+            let blocked = File::ALL
+                .into_iter()
+                .filter(|file| (lowest..=highest).contains(file))
+                .map(|file| Square::new(file, king.rank()))
+                .any(|c| c != king && c != rook && self.board.contains_key(&c));
+            if blocked {
+                continue;
+            }
+
+            let (from, to) = (
+                king.file().min(king_to.file()),
+                king.file().max(king_to.file()),
+            );
+            let attacked = File::ALL
+                .into_iter()
+                .filter(|file| (from..=to).contains(file))
+                .map(|file| Square::new(file, king.rank()))
+                .any(|c| is_attacked(&self.board, c, color.opposite()));
+            if attacked {
+                continue;
+            }
+
+            if let Some(m) = self.castling_move(kingside) {
+                moves.push(m);
+            }
         }
+
+        moves
     }
 }
 
-impl Display for Game {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "  ")?;
-        for file_letter in 'a'..'i' {
-            write!(f, " {} ", file_letter)?;
+/// moves the figures on `board` without any checks, takes care of the rook when castling,
+/// of the pawn taken en passant and of promotions
+fn move_figures(board: &mut HashMap<Square, Figure>, m: &Move, special: Special) {
+    let Some(mut figure) = board.remove(&m.from) else {
+        return;
+    };
+
+    match special {
+        Special::Castling {
+            rook_from,
+            rook_to,
+            king_to,
+        } => {
+            let rook = board.remove(&rook_from);
+            board.insert(king_to, figure);
+            if let Some(rook) = rook {
+                board.insert(rook_to, rook);
+            }
+            return;
+        }
+        Special::EnPassant(taken) => {
+            board.remove(&taken);
         }
-        writeln!(f)?;
+        Special::None => (),
+    }
 
-        for rank_idx in (1..9).rev() {
-            write!(f, "{} ", rank_idx)?;
+    if let Some(promotion) = m.promotion {
+        figure.variant = promotion;
+    }
 
-            for file_letter in 'a'..'i' {
-                match self.board.get(&(file_letter, rank_idx)) {
-                    Some(figure) => {
-                        let symbol = format!(" {} ", figure)
-                            .on_custom_color(get_bg_color((file_letter, rank_idx)));
-                        write!(f, "{}", symbol)?;
-                    }
-                    None => {
-                        let symbol = "   ".on_custom_color(get_bg_color((file_letter, rank_idx)));
-                        write!(f, "{}", symbol)?;
+    board.insert(m.to, figure);
+}
+
+fn is_attacked(board: &HashMap<Square, Figure>, square: Square, by: FigureColor) -> bool {
+    let has = |c: Option<Square>, variants: &[FigureVariant]| {
+        c.and_then(|c| board.get(&c))
+            .is_some_and(|f| f.color == by && variants.contains(&f.variant))
+    };
+
+    let pawn_dir = by.pawn_direction();
+    if [-1, 1]
+        .iter()
+        .any(|x_diff| has(square.offset(*x_diff, -pawn_dir), &[FigureVariant::Pawn]))
+    {
+        return true;
+    }
+
+    if KNIGHT_JUMPS
+        .iter()
+        .any(|(x, y)| has(square.offset(*x, *y), &[FigureVariant::Knight]))
+    {
+        return true;
+    }
+
+    if KING_STEPS
+        .iter()
+        .any(|(x, y)| has(square.offset(*x, *y), &[FigureVariant::King]))
+    {
+        return true;
+    }
+
+    let rays = [
+        (&ROOK_DIRECTIONS, FigureVariant::Rook),
+        (&BISHOP_DIRECTIONS, FigureVariant::Bishop),
+    ];
+    for (directions, slider) in rays {
+        for (x, y) in directions {
+            let mut curr = square.offset(*x, *y);
+            while let Some(c) = curr {
+                if let Some(figure) = board.get(&c) {
+                    if figure.color == by
+                        && (figure.variant == slider || figure.variant == FigureVariant::Queen)
+                    {
+                        return true;
                     }
+                    break;
                 }
+                curr = c.offset(*x, *y);
             }
-            write!(f, " {}", rank_idx)?;
-            writeln!(f)?;
         }
-        write!(f, "  ")?;
+    }
 
-        for file_letter in 'a'..'i' {
-            write!(f, " {} ", file_letter)?;
+    false
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for Game {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render(&RenderOptions::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the number of move sequences `depth` half-moves long
+    fn perft(game: &Game, depth: u32) -> u64 {
+        let moves = game.legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
         }
+        moves
+            .into_iter()
+            .map(|m| {
+                let mut next = game.clone();
+                next.play(m);
+                perft(&next, depth - 1)
+            })
+            .sum()
+    }
 
-        Ok(())
+    fn perft_fen(fen: &str, depth: u32) -> u64 {
+        perft(&Game::from_fen(fen).unwrap(), depth)
+    }
+
+    #[test]
+    fn perft_start() {
+        let game = Game::new();
+        assert_eq!(perft(&game, 1), 20);
+        assert_eq!(perft(&game, 2), 400);
+        assert_eq!(perft(&game, 3), 8902);
+    }
+
+    #[test]
+    fn perft_kiwipete() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        assert_eq!(perft_fen(fen, 1), 48);
+        assert_eq!(perft_fen(fen, 2), 2039);
+        assert_eq!(perft_fen(fen, 3), 97862);
+    }
+
+    #[test]
+    fn perft_en_passant() {
+        let fen = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+        assert_eq!(perft_fen(fen, 1), 14);
+        assert_eq!(perft_fen(fen, 2), 191);
+        assert_eq!(perft_fen(fen, 3), 2812);
+    }
+
+    #[test]
+    fn perft_promotions() {
+        let fen = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+        assert_eq!(perft_fen(fen, 1), 6);
+        assert_eq!(perft_fen(fen, 2), 264);
+        assert_eq!(perft_fen(fen, 3), 9467);
+        let fen = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+        assert_eq!(perft_fen(fen, 1), 44);
+        assert_eq!(perft_fen(fen, 2), 1486);
+    }
+
+    #[test]
+    fn illegal_moves() {
+        let mut game = Game::new();
+        assert!(game.apply_move("e2e5".parse().unwrap()).is_err());
+        assert!(game.apply_move("e7e5".parse().unwrap()).is_err());
+        assert!(game.apply_move("e1e2".parse().unwrap()).is_err());
+        assert!(game.history().is_empty());
     }
 }
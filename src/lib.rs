@@ -0,0 +1,39 @@
+mod book;
+mod chess960;
+mod clock;
+mod engine;
+mod epd;
+mod eval;
+mod fen;
+mod figure;
+mod game;
+mod moves;
+mod pgn;
+mod puzzle;
+#[cfg(feature = "serde")]
+mod record;
+mod render;
+mod review;
+mod san;
+mod setup;
+mod square;
+mod validate;
+mod zobrist;
+
+pub use book::Book;
+pub use chess960::STANDARD_POSITION;
+pub use clock::{format_duration, Clock};
+pub use engine::{Engine, SearchInfo, MATE, MAX_ELO, MAX_LEVEL, MAX_LEVEL_DEPTH, MIN_ELO};
+pub use epd::Epd;
+pub use eval::{figure_value, Evaluation, Term};
+pub use fen::STARTING_FEN;
+pub use figure::{Figure, FigureColor, FigureVariant};
+pub use game::{BoardSetup, CastlingRights, Game, GameState};
+pub use moves::Move;
+pub use pgn::parse_tags;
+pub use puzzle::Puzzle;
+pub use render::{BoardView, Orientation, RenderOptions, Theme};
+pub use review::{Judgement, MoveReview, Review};
+pub use setup::Setup;
+pub use square::{File, Rank, Square};
+pub use validate::ValidationError;
@@ -1,10 +1,171 @@
-use game::Game;
+use std::{
+    env,
+    io::{stdin, stdout, IsTerminal},
+    path::Path,
+};
 
-mod figure;
-mod game;
+use chess::{Book, Clock, FigureColor, Game, Orientation, RenderOptions, Theme};
 
-fn main() -> Result<(), ()> {
-    let mut g = Game::new();
-    g.game_loop();
+mod arena;
+mod cli;
+mod editor;
+mod net;
+mod observer;
+mod suite;
+mod trainer;
+mod tui;
+
+fn main() -> Result<(), String> {
+    // the panel next to the board in line mode, the full-screen ui draws one of its own
+    let mut options = RenderOptions {
+        panel: true,
+        ..Default::default()
+    };
+    let mut line_mode = !stdin().is_terminal() || !stdout().is_terminal();
+    let mut clock = Clock::unlimited();
+    let mut timed = false;
+    let mut game = Game::new();
+    let mut setup = false;
+    let mut resume = None;
+    let mut book = None;
+    let mut book_depth = None;
+    let mut hints = None;
+    let mut bot = None;
+    let mut me = FigureColor::White;
+
+    let mut args = env::args().skip(1).peekable();
+    match args.peek().map(String::as_str) {
+        Some("match") => return arena::run(args.skip(1)),
+        Some("review") => return cli::review(args.skip(1)),
+        Some("puzzles") => return trainer::run(args.skip(1)),
+        Some("epd") => return suite::run(args.skip(1)),
+        Some("host") => return net::host(args.skip(1).peekable()),
+        Some("join") => return net::join(args.skip(1)),
+        Some("observe") => return observer::run(args.skip(1)),
+        _ => (),
+    }
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--flip" => options.orientation = Orientation::SideToMove,
+            "--ascii" => options.theme = Theme::ascii(),
+            "--theme" => {
+                let name = args.next().unwrap_or_default();
+                options.theme = Theme::by_name(&name).ok_or(format!(
+                    "unknown theme '{}', pick one of {}",
+                    name,
+                    Theme::NAMES.join(", ")
+                ))?;
+            }
+            "--line" => line_mode = true,
+            "--clock" => {
+                let control = args.next().unwrap_or_default();
+                clock = Clock::from_time_control(&control)?;
+                timed = true;
+            }
+            "--960" => {
+                game = match args.peek().and_then(|n| n.parse().ok()) {
+                    Some(index) => {
+                        args.next();
+                        Game::new_chess960(index)?
+                    }
+                    None => Game::new_chess960_random(),
+                };
+            }
+            "--book" => {
+                let path = args.next().ok_or("missing file name for --book")?;
+                book = Some(Book::open(&path)?);
+            }
+            "--book-depth" => {
+                book_depth = Some(
+                    args.next()
+                        .and_then(|n| n.parse().ok())
+                        .ok_or("--book-depth needs a number")?,
+                )
+            }
+            "--hints" => {
+                hints = Some(
+                    args.next()
+                        .and_then(|n| n.parse().ok())
+                        .ok_or("--hints needs a number")?,
+                )
+            }
+            "--bot" => bot = Some(arena::engine(&args.next().unwrap_or_default())?),
+            "--black" => me = FigureColor::Black,
+            "--setup" => setup = true,
+            "--resume" => {
+                let path = args.next().ok_or("missing file name for --resume")?;
+                if Path::new(&path).exists() {
+                    game = cli::load(&path)?;
+                }
+                resume = Some(path);
+            }
+            "--fen" => {
+                game = Game::from_fen(&args.next().unwrap_or_default())?;
+                game.validate().map_err(|errors| {
+                    let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                    format!("the position cannot be played: {}", errors.join(", "))
+                })?;
+            }
+            _ => return Err(format!("unknown argument '{}'", arg)),
+        }
+    }
+
+    if let Some(depth) = book_depth {
+        book = book.map(|book: Book| book.with_max_depth(depth));
+    }
+    let bot = match bot {
+        Some(engine) => Some(cli::Bot {
+            engine: match &book {
+                Some(book) => engine.with_book(book.clone()),
+                None => engine,
+            },
+            color: me.opposite(),
+        }),
+        None if me == FigureColor::Black => return Err("--black needs a --bot to play".to_string()),
+        None => None,
+    };
+    // playing black against the bot, black is drawn at the bottom unless --flip says otherwise
+    if me == FigureColor::Black && options.orientation == Orientation::White {
+        options.orientation = Orientation::Black;
+    }
+
+    if line_mode && timed {
+        return Err("--clock needs the full-screen ui, not --line or piped input".to_string());
+    }
+
+    if setup {
+        match editor::setup_loop(&game, &options) {
+            Some(edited) => game = edited,
+            None => return Ok(()),
+        }
+    }
+
+    if line_mode {
+        cli::game_loop(
+            &mut game,
+            &options,
+            book.as_ref(),
+            &mut cli::Hints::new(hints),
+            bot.as_ref(),
+        );
+    } else {
+        tui::run(
+            &mut game,
+            &options,
+            clock,
+            book.as_ref(),
+            bot.as_ref(),
+            &mut cli::Hints::new(hints),
+        )
+        .map_err(|e| e.to_string())?;
+        if game.is_over() {
+            cli::print_result(&game, &options);
+        }
+    }
+
+    if let Some(path) = resume {
+        cli::save(&game, &path)?;
+    }
     Ok(())
 }
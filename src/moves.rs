@@ -0,0 +1,92 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{figure::FigureVariant, square::Square};
+
+/// A move in coordinate notation, eg `a1a2`, or `a7a8q` when a pawn gets promoted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<FigureVariant>,
+}
+
+impl Move {
+    pub fn new(from: Square, to: Square) -> Self {
+        Move {
+            from,
+            to,
+            promotion: None,
+        }
+    }
+
+    pub fn with_promotion(from: Square, to: Square, promotion: FigureVariant) -> Self {
+        Move {
+            from,
+            to,
+            promotion: Some(promotion),
+        }
+    }
+}
+
+impl FromStr for Move {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (Some(from), Some(to), Some(rest)) = (s.get(..2), s.get(2..4), s.get(4..)) else {
+            return Err("a move is two fields like e2e4, optionally followed by a promotion");
+        };
+
+        let from = from.parse()?;
+        let to = to.parse()?;
+        let mut chars = rest.chars();
+
+        let promotion = match chars.next() {
+            Some(c) => Some(
+                FigureVariant::from_char(c)
+                    .filter(|v| FigureVariant::PROMOTIONS.contains(v))
+                    .ok_or("unable to parse 'promotion', it has to be one of q, r, b, n")?,
+            ),
+            None => None,
+        };
+
+        if chars.next().is_some() {
+            return Err("unexpected characters after the move");
+        }
+
+        Ok(Move {
+            from,
+            to,
+            promotion,
+        })
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(promotion) = self.promotion {
+            write!(f, "{}", promotion.to_char())?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Move> for String {
+    fn from(m: Move) -> Self {
+        m.to_string()
+    }
+}
+
+impl TryFrom<String> for Move {
+    type Error = &'static str;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
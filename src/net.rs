@@ -0,0 +1,424 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    iter::Peekable,
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use chess::{FigureColor, Game, Orientation, RenderOptions, Theme};
+use colored::Colorize;
+
+use crate::cli::{print_result, read_line, turn};
+
+const PORT: u16 = 7878;
+const HOST_USAGE: &str = "usage: host [--port N] [--black] [--fen FEN] [--960 [0-959]] [--ascii]";
+const JOIN_USAGE: &str = "usage: join ADDRESS[:PORT] [--ascii]";
+
+/// the other player, talking over TCP one line at a time
+///
+/// the host starts with `game <color> <standard|chess960> <FEN>`, the color being the
+/// one the joining player gets, then both sides send `move <e2e4>` for their moves and
+/// `resign` to give up, closing the connection ends the game
+struct Peer {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+/// what the game waits for, whichever comes first
+enum Input {
+    /// a line the player typed, `None` once the input is closed
+    Typed(Option<String>),
+    /// a line from the other side, `None` once it disconnected
+    Received(Option<String>),
+}
+
+fn send(writer: &mut TcpStream, line: &str) -> Result<(), String> {
+    writeln!(writer, "{}", line).or(Err("the opponent disconnected".to_string()))
+}
+
+fn receive(reader: &mut BufReader<TcpStream>) -> Option<String> {
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim().to_string()),
+    }
+}
+
+/// hands the lines of `read` to `inputs` from a thread of its own until one is `None`
+fn forward(
+    inputs: Sender<Input>,
+    input: fn(Option<String>) -> Input,
+    mut read: impl FnMut() -> Option<String> + Send + 'static,
+) {
+    thread::spawn(move || loop {
+        let line = read();
+        let closed = line.is_none();
+        if inputs.send(input(line)).is_err() || closed {
+            return;
+        }
+    });
+}
+
+impl Peer {
+    fn new(stream: TcpStream) -> Result<Self, String> {
+        let writer = stream.try_clone().map_err(|e| e.to_string())?;
+        Ok(Peer {
+            reader: BufReader::new(stream),
+            writer,
+        })
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), String> {
+        send(&mut self.writer, line)
+    }
+
+    /// the next line from the other side, `None` once it disconnected
+    fn receive(&mut self) -> Option<String> {
+        receive(&mut self.reader)
+    }
+
+    /// reads the other side on a thread of its own, leaving the stream to write to
+    fn listen(self, inputs: Sender<Input>) -> TcpStream {
+        let Peer { mut reader, writer } = self;
+        forward(inputs, Input::Received, move || receive(&mut reader));
+        writer
+    }
+}
+
+/// tells the joining player the position and the color they get
+fn offer(peer: &mut Peer, game: &Game, me: FigureColor) -> Result<(), String> {
+    let variant = match game.is_chess960() {
+        true => "chess960",
+        false => "standard",
+    };
+    peer.send(&format!(
+        "game {} {} {}",
+        me.opposite().name(),
+        variant,
+        game.to_fen()
+    ))
+}
+
+/// the game the host offered and the color the joining player gets
+fn accept(peer: &mut Peer) -> Result<(Game, FigureColor), String> {
+    let start = peer.receive().ok_or("the host disconnected")?;
+    let unexpected = || format!("the host sent '{}'", start);
+    let mut fields = start.splitn(4, ' ');
+    let (Some("game"), Some(color), Some(variant), Some(fen)) =
+        (fields.next(), fields.next(), fields.next(), fields.next())
+    else {
+        return Err(unexpected());
+    };
+    let me = match color {
+        "white" => FigureColor::White,
+        "black" => FigureColor::Black,
+        _ => return Err(unexpected()),
+    };
+    let game = match variant {
+        "chess960" => Game::from_fen_chess960(fen)?,
+        "standard" => Game::from_fen(fen)?,
+        _ => return Err(unexpected()),
+    };
+    game.validate()
+        .map_err(|_| "the host sent a position that cannot be played".to_string())?;
+    Ok((game, me))
+}
+
+/// plays the game until it is over, someone resigns or the connection is lost, the
+/// player's input and the other side are read at the same time
+fn play(game: &mut Game, me: FigureColor, peer: Peer, options: &RenderOptions) {
+    let (inputs, events) = mpsc::channel();
+    forward(inputs.clone(), Input::Typed, read_line);
+    let mut writer = peer.listen(inputs);
+    play_inputs(game, me, &mut writer, &events, options);
+}
+
+fn play_inputs(
+    game: &mut Game,
+    me: FigureColor,
+    writer: &mut TcpStream,
+    events: &Receiver<Input>,
+    options: &RenderOptions,
+) {
+    let opponent = me.opposite();
+    println!("you play {}", me);
+    let mut moved = true;
+
+    while !game.is_over() {
+        if moved {
+            println!("{}", "----------------------------".cyan());
+            println!("{}", game.render(options));
+            if game.side_to_move() == me {
+                println!("your move, 'resign' gives up, 'fen' and 'pgn' print the game: ");
+            } else {
+                println!(
+                    "waiting for {}'s move, 'resign' gives up meanwhile",
+                    opponent
+                );
+            }
+        }
+        moved = false;
+
+        let Ok(input) = events.recv() else {
+            return;
+        };
+        match input {
+            Input::Typed(None) => return,
+            Input::Typed(Some(input)) => match input.trim() {
+                "resign" => {
+                    let _ = send(writer, "resign");
+                    println!("you resigned, {} wins", opponent);
+                    return;
+                }
+                "quit" => return,
+                "fen" => println!("{}", game.to_fen()),
+                "pgn" => println!("{}", game.to_pgn()),
+                _ if game.side_to_move() != me => {
+                    println!("{}", format!("err: it's {}'s turn", opponent).red())
+                }
+                input => match turn(game, input) {
+                    Ok(()) => {
+                        let m = game.last_move().expect("a move was just played");
+                        if let Err(e) = send(writer, &format!("move {}", m)) {
+                            println!("{}", e.red());
+                            return;
+                        }
+                        moved = true;
+                    }
+                    Err(e) => println!("{}", format!("err: {}", e).red()),
+                },
+            },
+            Input::Received(None) => {
+                println!("{}", "the opponent disconnected".red());
+                return;
+            }
+            Input::Received(Some(line)) => {
+                let mut words = line.split_whitespace();
+                match (words.next(), words.next()) {
+                    (Some("move"), Some(m)) if game.side_to_move() == opponent => {
+                        let played = m.parse().and_then(|m| game.apply_move(m));
+                        if let Err(e) = played {
+                            println!("{}", format!("the opponent sent {}: {}", m, e).red());
+                            return;
+                        }
+                        moved = true;
+                    }
+                    (Some("resign"), None) => {
+                        println!("{} resigned, you win", opponent);
+                        return;
+                    }
+                    _ => {
+                        println!("{}", format!("the opponent sent '{}'", line).red());
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    print_result(game, options);
+}
+
+fn options_for(me: FigureColor, ascii: bool) -> RenderOptions {
+    let mut options = RenderOptions {
+        orientation: match me {
+            FigureColor::White => Orientation::White,
+            FigureColor::Black => Orientation::Black,
+        },
+        panel: true,
+        ..Default::default()
+    };
+    if ascii {
+        options.theme = Theme::ascii();
+    }
+    options
+}
+
+/// waits for a player to join and plays against them
+pub fn host(mut args: Peekable<impl Iterator<Item = String>>) -> Result<(), String> {
+    let mut port = PORT;
+    let mut me = FigureColor::White;
+    let mut game = Game::new();
+    let mut ascii = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => {
+                port = args
+                    .next()
+                    .and_then(|p| p.parse().ok())
+                    .ok_or("--port needs a number")?
+            }
+            "--black" => me = FigureColor::Black,
+            "--ascii" => ascii = true,
+            "--fen" => {
+                game = Game::from_fen(&args.next().unwrap_or_default())?;
+                game.validate()
+                    .map_err(|_| "the position cannot be played".to_string())?;
+            }
+            "--960" => {
+                game = match args.peek().and_then(|n| n.parse().ok()) {
+                    Some(index) => {
+                        args.next();
+                        Game::new_chess960(index)?
+                    }
+                    None => Game::new_chess960_random(),
+                };
+            }
+            _ => return Err(format!("unknown argument '{}', {}", arg, HOST_USAGE)),
+        }
+    }
+
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .map_err(|e| format!("unable to listen on port {}: {}", port, e))?;
+    println!("waiting for a player to join on port {}", port);
+    let (stream, address) = listener.accept().map_err(|e| e.to_string())?;
+    println!("{} joined", address);
+
+    let mut peer = Peer::new(stream)?;
+    offer(&mut peer, &game, me)?;
+
+    play(&mut game, me, peer, &options_for(me, ascii));
+    Ok(())
+}
+
+/// connects to a host and plays against them
+pub fn join(args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut address = None;
+    let mut ascii = false;
+    for arg in args {
+        match arg.as_str() {
+            "--ascii" => ascii = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown argument '{}'", arg)),
+            _ if address.is_none() => address = Some(arg),
+            _ => return Err(JOIN_USAGE.to_string()),
+        }
+    }
+
+    let mut address = address.ok_or(JOIN_USAGE)?;
+    if !address.contains(':') {
+        address = format!("{}:{}", address, PORT);
+    }
+    let stream = TcpStream::connect(&address)
+        .map_err(|e| format!("unable to connect to {}: {}", address, e))?;
+    let mut peer = Peer::new(stream)?;
+    let (mut game, me) = accept(&mut peer)?;
+
+    play(&mut game, me, peer, &options_for(me, ascii));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    /// a host and a joining player connected over loopback
+    fn connect() -> (Peer, Peer) {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+        let joining = thread::spawn(move || TcpStream::connect(address).unwrap());
+        let (stream, _) = listener.accept().unwrap();
+        let host = Peer::new(stream).unwrap();
+        let guest = Peer::new(joining.join().unwrap()).unwrap();
+        (host, guest)
+    }
+
+    #[test]
+    fn host_and_join() {
+        let (mut host, mut guest) = connect();
+        let mut game = Game::new();
+        offer(&mut host, &game, FigureColor::Black).unwrap();
+        let (mut joined, me) = accept(&mut guest).unwrap();
+        assert_eq!(me, FigureColor::White);
+        assert!(!joined.is_chess960());
+        assert_eq!(joined.to_fen(), game.to_fen());
+
+        joined.apply_move("e2e4".parse().unwrap()).unwrap();
+        guest.send("move e2e4").unwrap();
+        assert_eq!(host.receive().as_deref(), Some("move e2e4"));
+        game.apply_move("e2e4".parse().unwrap()).unwrap();
+        assert_eq!(joined.to_fen(), game.to_fen());
+
+        drop(guest);
+        assert_eq!(host.receive(), None);
+    }
+
+    #[test]
+    fn join_chess960() {
+        let (mut host, mut guest) = connect();
+        // castling with the rooks where standard chess has them still follows Chess960 rules
+        let game = Game::new_chess960(518).unwrap();
+        offer(&mut host, &game, FigureColor::White).unwrap();
+        let (joined, me) = accept(&mut guest).unwrap();
+        assert_eq!(me, FigureColor::Black);
+        assert!(joined.is_chess960());
+        assert_eq!(joined.to_fen(), game.to_fen());
+    }
+
+    #[test]
+    fn join_rejects_bad_games() {
+        for line in [
+            "game white standard 8/8/8/8/8/8/8/8 w - - 0 1",
+            "game white standard 4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+            "game white crazyhouse rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "game red standard rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "hello",
+        ] {
+            let (mut host, mut guest) = connect();
+            host.send(line).unwrap();
+            assert!(accept(&mut guest).is_err(), "{}", line);
+        }
+        let (host, mut guest) = connect();
+        drop(host);
+        assert!(accept(&mut guest).is_err());
+    }
+
+    #[test]
+    fn resign_while_waiting() {
+        let (host, mut guest) = connect();
+        let (inputs, events) = mpsc::channel();
+        let mut writer = host.listen(inputs.clone());
+        inputs.send(Input::Typed(Some("e2e4".to_string()))).unwrap();
+        // black's move has not come yet, the host gives up anyway
+        inputs.send(Input::Typed(Some("e7e5".to_string()))).unwrap();
+        inputs
+            .send(Input::Typed(Some("resign".to_string())))
+            .unwrap();
+
+        let mut game = Game::new();
+        let options = RenderOptions::default();
+        play_inputs(
+            &mut game,
+            FigureColor::White,
+            &mut writer,
+            &events,
+            &options,
+        );
+        assert_eq!(game.history().len(), 1);
+        assert_eq!(guest.receive().as_deref(), Some("move e2e4"));
+        assert_eq!(guest.receive().as_deref(), Some("resign"));
+    }
+
+    #[test]
+    fn disconnects_end_the_turn() {
+        let (host, guest) = connect();
+        let (inputs, events) = mpsc::channel();
+        let mut writer = host.listen(inputs);
+        // nothing gets typed, the game still ends when the other side goes away
+        drop(guest);
+
+        let mut game = Game::new();
+        let options = RenderOptions::default();
+        play_inputs(
+            &mut game,
+            FigureColor::White,
+            &mut writer,
+            &events,
+            &options,
+        );
+        assert!(game.history().is_empty());
+    }
+}
@@ -0,0 +1,353 @@
+use std::{
+    fs,
+    io::{stdout, IsTerminal},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use chess::{format_duration, parse_tags, Engine, FigureColor, Game, RenderOptions};
+use colored::Colorize;
+use crossterm::{
+    cursor, execute,
+    terminal::{Clear, ClearType},
+};
+
+const USAGE: &str = "usage: observe GAME.pgn [--depth N]";
+#[cfg(feature = "server")]
+const SERVER_USAGE: &str = "usage: observe --server ADDRESS:PORT GAME [--depth N]";
+
+/// how often the PGN file is read again
+const POLL: Duration = Duration::from_millis(500);
+
+/// the game as the source last reported it
+#[derive(Clone)]
+struct Snapshot {
+    title: String,
+    game: Game,
+    /// white's and black's time left when the snapshot was taken
+    clocks: Option<[Duration; 2]>,
+    result: Option<String>,
+}
+
+impl Snapshot {
+    /// whether nothing worth redrawing changed, a file can be read while half written
+    fn same(&self, other: &Snapshot) -> bool {
+        self.game.history() == other.game.history()
+            && self.clocks == other.clocks
+            && self.result == other.result
+    }
+
+    /// whether the clock of the side to move is running
+    fn ticking(&self) -> bool {
+        self.clocks.is_some() && self.result.is_none() && !self.game.is_over()
+    }
+}
+
+enum Update {
+    Game(Snapshot),
+    #[cfg(feature = "server")]
+    Lost(String),
+}
+
+/// the time after the last `[%clk 0:04:59]` comment of each side
+fn pgn_clocks(pgn: &str, game: &Game) -> Option<[Duration; 2]> {
+    // a comment that is no time still belongs to its move, it is only skipped
+    let clocks: Vec<Option<Duration>> = pgn
+        .split("[%clk ")
+        .skip(1)
+        .map(|rest| {
+            let time = rest.split(']').next()?.trim();
+            let mut seconds = 0.0;
+            for part in time.split(':') {
+                seconds = seconds * 60.0 + part.parse::<f64>().ok()?;
+            }
+            Duration::try_from_secs_f64(seconds).ok()
+        })
+        .collect();
+
+    // the clocks belong to the first moves, alternating from the side that moved first
+    let moves = game.history().len();
+    let first = match moves % 2 {
+        0 => game.side_to_move(),
+        _ => game.side_to_move().opposite(),
+    };
+    let mut times = [None, None];
+    for (i, clock) in clocks.into_iter().enumerate().take(moves) {
+        let color = match i % 2 {
+            0 => first,
+            _ => first.opposite(),
+        };
+        if clock.is_some() {
+            times[color.index()] = clock;
+        }
+    }
+    Some([times[0]?, times[1]?])
+}
+
+fn read_pgn(path: &str) -> Result<Snapshot, String> {
+    let pgn = fs::read_to_string(path).map_err(|_| format!("unable to read '{}'", path))?;
+    let game = Game::from_pgn(&pgn)?;
+    let tags = parse_tags(&pgn);
+    let tag = |name: &str| {
+        tags.iter()
+            .find(|(n, _)| n == name)
+            .map_or("?", |(_, v)| v.as_str())
+            .to_string()
+    };
+
+    Ok(Snapshot {
+        title: format!("{}: {} - {}", tag("Event"), tag("White"), tag("Black")),
+        clocks: pgn_clocks(&pgn, &game),
+        result: Some(tag("Result")).filter(|r| r != "*" && r != "?"),
+        game,
+    })
+}
+
+/// reads the file again whenever it changes, a file being written may not parse for a moment
+fn watch_pgn(path: String, updates: Sender<Update>) {
+    let mut last = None;
+    loop {
+        if let Ok(text) = fs::read_to_string(&path) {
+            if last.as_ref() != Some(&text) {
+                if let Ok(snapshot) = read_pgn(&path) {
+                    if updates.send(Update::Game(snapshot)).is_err() {
+                        return;
+                    }
+                    last = Some(text);
+                }
+            }
+        }
+        thread::sleep(POLL);
+    }
+}
+
+/// follows a game of `chess-server` as a spectator
+#[cfg(feature = "server")]
+fn watch_server(address: String, id: u32, updates: Sender<Update>) {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpStream,
+    };
+
+    let lost = |message: String| {
+        let _ = updates.send(Update::Lost(message));
+    };
+    let mut stream = match TcpStream::connect(&address) {
+        Ok(stream) => stream,
+        Err(e) => return lost(format!("unable to connect to {}: {}", address, e)),
+    };
+    if writeln!(stream, r#"{{"type": "watch", "game": {}}}"#, id).is_err() {
+        return lost("the server disconnected".to_string());
+    }
+
+    let mut snapshot = None;
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(&line) else {
+            continue;
+        };
+        let millis = |side: &str| event[side].as_u64().map(Duration::from_millis);
+
+        match event["type"].as_str() {
+            Some("update") => {
+                let Ok(game) = serde_json::from_value::<Game>(event["position"].clone()) else {
+                    continue;
+                };
+                let clocks = millis("white_ms")
+                    .zip(millis("black_ms"))
+                    .map(|(w, b)| [w, b]);
+                snapshot = Some(Snapshot {
+                    title: format!("game {} on {}", id, address),
+                    game,
+                    clocks,
+                    result: None,
+                });
+            }
+            Some("over") => {
+                if let Some(snapshot) = &mut snapshot {
+                    let result = event["result"].as_str().unwrap_or_default();
+                    let reason = event["reason"].as_str().unwrap_or_default();
+                    snapshot.result = Some(format!("{} ({})", result, reason));
+                }
+            }
+            Some("error") => {
+                return lost(event["message"].as_str().unwrap_or_default().to_string());
+            }
+            _ => continue,
+        }
+
+        let Some(snapshot) = &snapshot else {
+            continue;
+        };
+        if updates.send(Update::Game(snapshot.clone())).is_err() {
+            return;
+        }
+    }
+    lost("the server disconnected".to_string())
+}
+
+/// the score of the position for white, in pawns or as a mate, and the best move
+fn evaluate(game: &Game, depth: u32) -> Option<String> {
+    let info = Engine::new(depth).analyze_for(game, depth, Duration::from_secs(2), |_| ())?;
+    let score = match game.side_to_move() {
+        FigureColor::White => info.score,
+        FigureColor::Black => -info.score,
+    };
+    let score = match info.mate_in() {
+        Some(moves) => {
+            let white_mates = (moves > 0) == (game.side_to_move() == FigureColor::White);
+            let sign = if white_mates { "+" } else { "-" };
+            format!("{}M{}", sign, moves.abs())
+        }
+        None => format!("{:+.2}", score as f64 / 100.0),
+    };
+    let best = info.best_move().map_or(String::new(), |m| game.san(&m));
+    Some(format!("{}, best {} (depth {})", score, best, info.depth))
+}
+
+fn draw(snapshot: &Snapshot, since: Instant, eval: Option<&str>) {
+    if stdout().is_terminal() {
+        let _ = execute!(stdout(), Clear(ClearType::All), cursor::MoveTo(0, 0));
+    } else {
+        println!("{}", "----------------------------".cyan());
+    }
+
+    let game = &snapshot.game;
+    println!("{}", snapshot.title.bold());
+    let options = RenderOptions {
+        panel: true,
+        ..Default::default()
+    };
+    println!("{}", game.render(&options));
+
+    if let Some(clocks) = snapshot.clocks {
+        let running = snapshot.ticking();
+        let shown = |color: FigureColor| {
+            let clock = clocks[color.index()];
+            match running && color == game.side_to_move() {
+                true => format_duration(clock.saturating_sub(since.elapsed())),
+                false => format_duration(clock),
+            }
+        };
+        println!(
+            "white {} | black {}",
+            shown(FigureColor::White),
+            shown(FigureColor::Black)
+        );
+    }
+
+    // the panel next to the board only knows how the moves ended the game
+    if let Some(result) = &snapshot.result {
+        println!("game over, {}", result);
+    }
+    if let Some(eval) = eval {
+        println!("eval {}", eval);
+    }
+}
+
+/// redraws the board after every move of a game played elsewhere, until interrupted
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut path = None;
+    #[cfg(feature = "server")]
+    let mut server = None;
+    let mut depth = 3;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => {
+                depth = args
+                    .next()
+                    .and_then(|d| d.parse().ok())
+                    .ok_or("--depth needs a number")?
+            }
+            #[cfg(feature = "server")]
+            "--server" => {
+                let address = args.next().ok_or(SERVER_USAGE)?;
+                let id: u32 = args
+                    .next()
+                    .and_then(|id| id.parse().ok())
+                    .ok_or(SERVER_USAGE)?;
+                server = Some((address, id));
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown argument '{}'", arg)),
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let (sender, updates): (Sender<Update>, Receiver<Update>) = mpsc::channel();
+    #[cfg(feature = "server")]
+    if let Some((address, id)) = server {
+        if path.is_some() {
+            return Err(SERVER_USAGE.to_string());
+        }
+        thread::spawn(move || watch_server(address, id, sender));
+        return follow(updates, depth);
+    }
+
+    let path = path.ok_or(USAGE)?;
+    // the first read fails loudly, later ones just wait for the file to be readable again
+    read_pgn(&path)?;
+    thread::spawn(move || watch_pgn(path, sender));
+    follow(updates, depth)
+}
+
+fn follow(updates: Receiver<Update>, depth: u32) -> Result<(), String> {
+    let mut current: Option<(Snapshot, Instant, Option<String>)> = None;
+
+    loop {
+        match updates.recv_timeout(Duration::from_secs(1)) {
+            Ok(Update::Game(snapshot))
+                if current.as_ref().is_some_and(|(s, ..)| s.same(&snapshot)) =>
+            {
+                continue
+            }
+            Ok(Update::Game(snapshot)) => {
+                let eval = match snapshot.game.is_over() || snapshot.result.is_some() {
+                    true => None,
+                    false => evaluate(&snapshot.game, depth),
+                };
+                current = Some((snapshot, Instant::now(), eval));
+            }
+            #[cfg(feature = "server")]
+            Ok(Update::Lost(message)) => return Err(message),
+            // nothing changed, but a running clock still needs redrawing
+            Err(RecvTimeoutError::Timeout) => {
+                if !current.as_ref().is_some_and(|(s, ..)| s.ticking()) {
+                    continue;
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+
+        if let Some((snapshot, since, eval)) = &current {
+            draw(snapshot, *since, eval.as_deref());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clocks_skip_bad_comments() {
+        let pgn = "1. e4 {[%clk 0:05:00]} e5 {[%clk 0:04:58]} 2. Nf3 {[%clk nan]} \
+                   Nc6 {[%clk 1e300]} 3. Bb5 {[%clk -1]} *";
+        let game = Game::from_pgn(pgn).unwrap();
+        let clocks = pgn_clocks(pgn, &game).unwrap();
+        assert_eq!(clocks, [Duration::from_secs(300), Duration::from_secs(298)]);
+
+        let pgn = "1. e4 {[%clk 0:04:59.5]} e5 {[%clk 1:00:00]} 2. Nf3 {[%clk 0:04:50]} *";
+        let game = Game::from_pgn(pgn).unwrap();
+        let clocks = pgn_clocks(pgn, &game).unwrap();
+        assert_eq!(
+            clocks,
+            [Duration::from_secs(290), Duration::from_secs(3600)]
+        );
+        assert_eq!(pgn_clocks("1. e4 {[%clk x]} *", &game), None);
+    }
+}
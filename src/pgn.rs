@@ -0,0 +1,356 @@
+use crate::{
+    fen::STARTING_FEN,
+    figure::FigureColor,
+    game::{Game, GameState},
+};
+
+/// the PGN result token of the game, `*` while it is still going on
+pub fn result(game: &Game) -> &'static str {
+    match game.state() {
+        GameState::Checkmate(FigureColor::White) => "0-1",
+        GameState::Checkmate(FigureColor::Black) => "1-0",
+        GameState::Stalemate(_) => "1/2-1/2",
+        GameState::OutOfTime(FigureColor::White) => "0-1",
+        GameState::OutOfTime(FigureColor::Black) => "1-0",
+        _ => "*",
+    }
+}
+
+/// splits PGN movetext into move tokens, leaving out comments, variations, NAGs,
+/// move numbers and the result
+fn movetext_tokens(movetext: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut depth = 0;
+    let mut chars = movetext.chars().peekable();
+    let mut curr = String::new();
+
+    let flush = |curr: &mut String, tokens: &mut Vec<String>| {
+        let is_number = curr.chars().all(|c| c.is_ascii_digit() || c == '.');
+        let is_result = matches!(curr.as_str(), "1-0" | "0-1" | "1/2-1/2" | "*");
+        if !is_number && !is_result && !curr.starts_with('$') {
+            tokens.push(curr.clone());
+        }
+        curr.clear();
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                flush(&mut curr, &mut tokens);
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            ';' => {
+                flush(&mut curr, &mut tokens);
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' => {
+                flush(&mut curr, &mut tokens);
+                depth += 1;
+            }
+            ')' => {
+                curr.clear();
+                depth -= 1;
+            }
+            _ if depth > 0 => (),
+            c if c.is_whitespace() => flush(&mut curr, &mut tokens),
+            c => {
+                curr.push(c);
+                // a move number glued to the move, eg `1.e4`
+                if c == '.'
+                    && chars.peek().is_some_and(|n| *n != '.')
+                    && curr.chars().all(|c| c.is_ascii_digit() || c == '.')
+                {
+                    curr.clear();
+                }
+            }
+        }
+    }
+    flush(&mut curr, &mut tokens);
+
+    tokens
+}
+
+/// whether the line belongs to the tag pairs before the moves
+fn is_header(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('[')
+}
+
+/// reads the tag pairs of a PGN, eg `[White "Carlsen"]`
+pub fn parse_tags(pgn: &str) -> Vec<(String, String)> {
+    pgn.lines()
+        .take_while(|l| is_header(l))
+        .map(str::trim)
+        .filter(|l| l.starts_with('[') && l.ends_with(']'))
+        .filter_map(|l| {
+            let (name, value) = l[1..l.len() - 1].split_once(' ')?;
+            let value = value.trim();
+            // only the outer quotes, the value may end in an escaped one
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value)
+                .replace("\\\"", "\"");
+            Some((name.to_string(), value))
+        })
+        .collect()
+}
+
+impl Game {
+    /// writes the game as PGN, with a FEN header when it did not start from the
+    /// standard position
+    pub fn to_pgn(&self) -> String {
+        self.to_pgn_with_tags(&[])
+    }
+
+    /// like [`Game::to_pgn`], with extra tags such as `White` or `Event` overriding the defaults
+    pub fn to_pgn_with_tags(&self, tags: &[(&str, &str)]) -> String {
+        self.annotated_pgn(tags, &[])
+    }
+
+    /// writes the game as PGN with `annotations[i]`, eg comments or variations, following
+    /// the `i`th move, a first annotation like `?!` is glued to the move
+    pub(crate) fn annotated_pgn(
+        &self,
+        tags: &[(&str, &str)],
+        annotations: &[Vec<String>],
+    ) -> String {
+        let result = result(self);
+        let mut header: Vec<(String, String)> = [
+            ("Event", "?"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", "?"),
+            ("White", "?"),
+            ("Black", "?"),
+            ("Result", result),
+        ]
+        .iter()
+        .map(|(n, v)| (n.to_string(), v.to_string()))
+        .collect();
+
+        if self.is_chess960() {
+            header.push(("Variant".to_string(), "Chess960".to_string()));
+        }
+        if let GameState::OutOfTime(_) = self.state() {
+            header.push(("Termination".to_string(), "time forfeit".to_string()));
+        }
+        if self.start_fen() != STARTING_FEN || self.is_chess960() {
+            header.push(("SetUp".to_string(), "1".to_string()));
+            header.push(("FEN".to_string(), self.start_fen().to_string()));
+        }
+
+        for (name, value) in tags {
+            match header.iter_mut().find(|(n, _)| n == name) {
+                Some(tag) => tag.1 = value.to_string(),
+                None => header.push((name.to_string(), value.to_string())),
+            }
+        }
+
+        let mut pgn = String::new();
+        for (name, value) in header {
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value.replace('"', "\\\"")));
+        }
+        pgn.push('\n');
+
+        let start = Game::from_fen(self.start_fen()).unwrap_or_default();
+        let mut number = start.turns() / 2 + 1;
+        let mut white = start.side_to_move() == FigureColor::White;
+
+        let mut tokens = Vec::new();
+        let mut annotated = false;
+        for (i, san) in self.san_history().into_iter().enumerate() {
+            if white {
+                tokens.push(format!("{}.", number));
+            } else if i == 0 || annotated {
+                // black's move number is repeated when something came between the moves
+                tokens.push(format!("{}...", number));
+            }
+            if !white {
+                number += 1;
+            }
+            white = !white;
+
+            let mut annotation = annotations.get(i).map_or(&[][..], |a| a.as_slice());
+            match annotation.split_first() {
+                Some((glyph, rest)) if glyph.chars().all(|c| c == '!' || c == '?') => {
+                    tokens.push(format!("{}{}", san, glyph));
+                    annotation = rest;
+                }
+                _ => tokens.push(san),
+            }
+            tokens.extend(annotation.iter().cloned());
+            annotated = !annotation.is_empty();
+        }
+        tokens.push(result.to_string());
+
+        pgn.push_str(&wrap(&tokens, 80));
+        pgn.push('\n');
+        pgn
+    }
+
+    /// reads the first game of a PGN, replaying its moves from the FEN header or the
+    /// standard position, a FEN header has to pass [`Game::validate`]
+    pub fn from_pgn(pgn: &str) -> Result<Self, &'static str> {
+        let tags = parse_tags(pgn);
+        let tag = |name: &str| {
+            tags.iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+        };
+
+        let mut game = match tag("FEN") {
+            Some(fen) => Game::from_fen(fen)?.validated()?,
+            None => Game::new(),
+        };
+        if tag("Variant").is_some_and(|v| v.eq_ignore_ascii_case("chess960")) {
+            game.chess960 = true;
+        }
+
+        // comments in the moves may start a line with `[` too, eg `[%eval 0.35]`
+        let movetext: String = pgn
+            .lines()
+            .skip_while(|l| is_header(l))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        for token in movetext_tokens(&movetext) {
+            let m = game.parse_san(&token)?;
+            game.apply_move(m)?;
+        }
+        // the side to move is the one whose clock ran out
+        if tag("Termination").is_some_and(|t| t.eq_ignore_ascii_case("time forfeit")) {
+            game.lose_on_time(game.side_to_move());
+        }
+
+        Ok(game)
+    }
+}
+
+/// joins the tokens with spaces into lines no longer than `width`
+fn wrap(tokens: &[String], width: usize) -> String {
+    let mut text = String::new();
+    let mut line_len = 0;
+    for token in tokens {
+        if line_len > 0 && line_len + 1 + token.len() > width {
+            text.push('\n');
+            line_len = 0;
+        } else if line_len > 0 {
+            text.push(' ');
+            line_len += 1;
+        }
+        text.push_str(token);
+        line_len += token.len();
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for m in moves {
+            let m = game.parse_san(m).unwrap();
+            game.apply_move(m).unwrap();
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &[
+                "e4", "e5", "Nf3", "Nc6", "Bc4", "Nf6", "Ng5", "d5", "exd5", "Nxd5",
+            ],
+        );
+        play(&mut game, &["Nxf7", "Kxf7", "Qf3+", "Ke6", "O-O"]);
+        let pgn = game.to_pgn_with_tags(&[("White", "Fried \"Liver\""), ("Black", "?")]);
+        let read = Game::from_pgn(&pgn).unwrap();
+        assert_eq!(read.history(), game.history());
+        assert_eq!(read.to_fen(), game.to_fen());
+        assert_eq!(read.to_pgn_with_tags(&[("White", "Fried \"Liver\"")]), pgn);
+        assert!(parse_tags(&pgn).contains(&("White".to_string(), "Fried \"Liver\"".to_string())));
+    }
+
+    #[test]
+    fn round_trips_from_fen() {
+        let fen = "4k3/1P6/8/8/8/8/6p1/4K3 b - - 0 40";
+        let mut game = Game::from_fen(fen).unwrap();
+        play(
+            &mut game,
+            &["g1=Q+", "Kd2", "Qd4+", "Ke2", "Qe4+", "Kd2", "Kd7", "b8=N+"],
+        );
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[FEN \"4k3/1P6/8/8/8/8/6p1/4K3 b - - 0 40\"]"));
+        assert!(pgn.contains("40... g1=Q+ 41. Kd2"));
+        let read = Game::from_pgn(&pgn).unwrap();
+        assert_eq!(read.to_fen(), game.to_fen());
+        assert_eq!(read.to_pgn(), pgn);
+    }
+
+    #[test]
+    fn round_trips_chess960() {
+        // the standard setup, castling is still written as the king taking its rook
+        let mut game = Game::new_chess960(518).unwrap();
+        play(
+            &mut game,
+            &["e4", "e5", "Nf3", "Nf6", "Bc4", "Bc5", "O-O", "O-O"],
+        );
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[Variant \"Chess960\"]"));
+        assert_eq!(game.history()[6].to_string(), "e1h1");
+        let read = Game::from_pgn(&pgn).unwrap();
+        assert!(read.is_chess960());
+        assert_eq!(read.to_fen(), game.to_fen());
+    }
+
+    #[test]
+    fn skips_comments_and_variations() {
+        let pgn = "[Event \"x\"]\n\n1.e4 {best by test} e5 (1... c5 2. Nf3 (2. c3)) \
+                   2. Nf3 $1 ; a comment\nNc6 3. Bb5 a6!? 1-0";
+        let game = Game::from_pgn(pgn).unwrap();
+        assert_eq!(game.san_history(), ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]);
+        assert!(Game::from_pgn("1. e4 e4").is_err());
+        assert!(Game::from_pgn("1. e4 e5 2. O-O").is_err());
+    }
+
+    #[test]
+    fn results() {
+        let mut game = Game::new();
+        play(&mut game, &["f3", "e5", "g4", "Qh4#"]);
+        assert_eq!(result(&game), "0-1");
+        assert!(game.to_pgn().trim_end().ends_with("Qh4# 0-1"));
+        assert_eq!(result(&Game::new()), "*");
+    }
+
+    #[test]
+    fn time_forfeit() {
+        let mut game = Game::new();
+        play(&mut game, &["e4", "e5"]);
+        game.lose_on_time(FigureColor::White);
+        assert_eq!(game.state(), GameState::OutOfTime(FigureColor::White));
+        assert!(game.is_over());
+        assert!(game.apply_move("g1f3".parse().unwrap()).is_err());
+
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[Termination \"time forfeit\"]"));
+        assert!(pgn.trim_end().ends_with("e5 0-1"));
+        assert_eq!(Game::from_pgn(&pgn).unwrap().state(), game.state());
+
+        // a mate stands even when the flag falls right after
+        let mut mated = Game::new();
+        play(&mut mated, &["f3", "e5", "g4", "Qh4#"]);
+        mated.lose_on_time(FigureColor::Black);
+        assert_eq!(mated.state(), GameState::Checkmate(FigureColor::White));
+    }
+}
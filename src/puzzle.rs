@@ -0,0 +1,158 @@
+use crate::{
+    epd::Epd,
+    game::{Game, GameState},
+    moves::Move,
+};
+
+/// a position to find the winning moves in
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    pub id: Option<String>,
+    pub rating: Option<u32>,
+    /// the position to solve, the side to move is the one looking for the moves
+    pub game: Game,
+    /// the moves to find, with the opponent's replies in between
+    pub solution: Vec<Move>,
+}
+
+/// plays the moves, written as `e2e4` or in SAN, and returns them the way
+/// [`Game::history`] has them
+fn replay(game: &Game, moves: &str) -> Result<Vec<Move>, &'static str> {
+    let mut position = game.clone();
+    moves
+        .split_whitespace()
+        .map(|token| {
+            let m = token
+                .parse()
+                .or_else(|e| position.parse_san(token).or(Err(e)))?;
+            position.apply_move(m)?;
+            position.last_move().ok_or("unable to read the solution")
+        })
+        .collect()
+}
+
+impl Puzzle {
+    /// reads an EPD line with the solution in a `pv` operation, or just its first move
+    /// in `bm`, and an optional `id`, the position has to pass [`Game::validate`]
+    pub fn from_epd(line: &str) -> Result<Self, &'static str> {
+        let epd: Epd = line.parse()?;
+        let moves = epd
+            .operation("pv")
+            .or(epd.operation("bm"))
+            .ok_or("the puzzle has no pv or bm operation")?;
+        let solution = replay(&epd.game, moves)?;
+        if solution.is_empty() {
+            return Err("the puzzle has no solution");
+        }
+
+        Ok(Puzzle {
+            id: epd.id().map(str::to_string),
+            rating: None,
+            game: epd.game,
+            solution,
+        })
+    }
+
+    /// reads a line of the Lichess puzzle database, `PuzzleId,FEN,Moves,Rating,...`, where
+    /// the first move is the opponent's and is played before the puzzle starts, the position
+    /// has to pass [`Game::validate`]
+    pub fn from_csv(line: &str) -> Result<Self, &'static str> {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [id, fen, moves, rest @ ..] = fields.as_slice() else {
+            return Err("a puzzle needs an id, a FEN and the moves");
+        };
+
+        let mut game = Game::from_fen(fen)?.validated()?;
+        let mut solution = replay(&game, moves)?;
+        if solution.len() < 2 {
+            return Err("the puzzle needs the opponent's move and a solution");
+        }
+        game.play(solution.remove(0));
+
+        Ok(Puzzle {
+            id: Some(id.to_string()),
+            rating: rest.first().and_then(|r| r.parse().ok()),
+            game,
+            solution,
+        })
+    }
+
+    /// whether `after`, the position after the solver's move at `step` of the solution,
+    /// is what the solution wants, any other move that mates is fine too
+    pub fn accepts(&self, step: usize, after: &Game) -> bool {
+        after.last_move() == self.solution.get(step).copied()
+            || matches!(after.state(), GameState::Checkmate(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(moves: &[Move]) -> Vec<String> {
+        moves.iter().map(Move::to_string).collect()
+    }
+
+    #[test]
+    fn from_epd() {
+        let puzzle =
+            Puzzle::from_epd("7k/8/8/8/8/8/R7/1R4K1 w - - pv Rb7 Kg8 a2a8; id \"ladder\";")
+                .unwrap();
+        assert_eq!(puzzle.id.as_deref(), Some("ladder"));
+        assert_eq!(puzzle.rating, None);
+        assert_eq!(moves(&puzzle.solution), ["b1b7", "h8g8", "a2a8"]);
+        assert_eq!(puzzle.game.to_fen(), "7k/8/8/8/8/8/R7/1R4K1 w - - 0 1");
+
+        let puzzle = Puzzle::from_epd("6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#;").unwrap();
+        assert_eq!(puzzle.id, None);
+        assert_eq!(moves(&puzzle.solution), ["a1a8"]);
+
+        assert_eq!(
+            Puzzle::from_epd("6k1/5ppp/8/8/8/8/8/R5K1 w - - id \"no solution\";").err(),
+            Some("the puzzle has no pv or bm operation")
+        );
+        assert!(Puzzle::from_epd("6k1/5ppp/8/8/8/8/8/R5K1 w - - pv Ra8# Kh8;").is_err());
+        assert!(Puzzle::from_epd("6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Rb8;").is_err());
+        // white is missing its king
+        assert!(Puzzle::from_epd("6k1/5ppp/8/8/8/8/8/R7 w - - bm Ra8#;").is_err());
+    }
+
+    #[test]
+    fn from_csv() {
+        let puzzle =
+            Puzzle::from_csv("abc12,r5k1/5ppp/8/8/8/8/8/4R1K1 b - - 0 1,a8a2 e1e8,1234,75,90,120")
+                .unwrap();
+        assert_eq!(puzzle.id.as_deref(), Some("abc12"));
+        assert_eq!(puzzle.rating, Some(1234));
+        // the opponent's move is already played
+        assert_eq!(moves(puzzle.game.history()), ["a8a2"]);
+        assert_eq!(moves(&puzzle.solution), ["e1e8"]);
+
+        let unrated = Puzzle::from_csv("x,r5k1/5ppp/8/8/8/8/8/4R1K1 b - - 0 1,a8a2 e1e8").unwrap();
+        assert_eq!(unrated.rating, None);
+
+        assert_eq!(
+            Puzzle::from_csv("x,r5k1/5ppp/8/8/8/8/8/4R1K1 b - - 0 1,a8a2").err(),
+            Some("the puzzle needs the opponent's move and a solution")
+        );
+        assert!(Puzzle::from_csv("x,r5k1/5ppp/8/8/8/8/8/4R1K1 b - - 0 1").is_err());
+        assert!(Puzzle::from_csv("x,r5k1/5ppp/8/8/8/8/8/4R1K1 b - - 0 1,a8a2 e1e9").is_err());
+        assert_eq!(
+            Puzzle::from_csv("x,r5k1/5ppp/8/8/8/8/8/4R3 b - - 0 1,a8a2 e1e8").err(),
+            Some("the position cannot be played")
+        );
+    }
+
+    #[test]
+    fn accepts_other_mates() {
+        let puzzle = Puzzle::from_epd("6k1/5ppp/8/8/8/8/8/R3R1K1 w - - bm Ra8#;").unwrap();
+        let after = |m: &str| {
+            let mut game = puzzle.game.clone();
+            game.apply_move(m.parse().unwrap()).unwrap();
+            game
+        };
+        assert!(puzzle.accepts(0, &after("a1a8")));
+        assert!(puzzle.accepts(0, &after("e1e8")));
+        assert!(!puzzle.accepts(0, &after("a1a7")));
+    }
+}
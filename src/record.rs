@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{Game, GameState},
+    moves::Move,
+};
+
+/// how a [`Game`] is serialized, the starting position and the moves played from it,
+/// plus the current position and state for readers that do not want to replay them
+#[derive(Serialize, Deserialize)]
+pub(crate) struct GameRecord {
+    start_fen: String,
+    #[serde(default)]
+    chess960: bool,
+    moves: Vec<Move>,
+    #[serde(default)]
+    fen: Option<String>,
+    #[serde(default)]
+    state: Option<GameState>,
+}
+
+impl From<Game> for GameRecord {
+    fn from(game: Game) -> Self {
+        GameRecord {
+            start_fen: game.start_fen().to_string(),
+            chess960: game.is_chess960(),
+            moves: game.history().to_vec(),
+            fen: Some(game.to_fen()),
+            state: Some(game.state()),
+        }
+    }
+}
+
+impl TryFrom<GameRecord> for Game {
+    type Error = &'static str;
+
+    /// replays the moves, so `fen` and `state` are only there for the readers, apart from
+    /// a loss on time
+    fn try_from(record: GameRecord) -> Result<Self, Self::Error> {
+        let mut game = Game::from_fen(&record.start_fen)?.validated()?;
+        game.chess960 |= record.chess960;
+        for m in record.moves {
+            game.apply_move(m)?;
+        }
+        // running out of time is the one result the moves do not tell
+        if let Some(GameState::OutOfTime(color)) = record.state {
+            game.lose_on_time(color);
+        }
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::DeserializeOwned;
+
+    use super::*;
+    use crate::figure::{Figure, FigureColor, FigureVariant};
+
+    fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+        let json = serde_json::to_string(value).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    fn assert_same_game(game: &Game) {
+        let read = round_trip(game);
+        assert_eq!(read.history(), game.history());
+        assert_eq!(read.to_fen(), game.to_fen());
+        assert_eq!(read.start_fen(), game.start_fen());
+        assert_eq!(read.state(), game.state());
+        assert_eq!(read.is_chess960(), game.is_chess960());
+    }
+
+    #[test]
+    fn games() {
+        let mut game = Game::new();
+        for san in [
+            "e4", "d5", "e5", "f5", "exf6", "Nc6", "fxg7", "Nf6", "gxh8=Q", "Bd7", "Nf3", "e5",
+            "Be2", "Qe7", "O-O", "O-O-O",
+        ] {
+            let m = game.parse_san(san).unwrap();
+            game.apply_move(m).unwrap();
+        }
+        assert_same_game(&game);
+        assert_same_game(&Game::new());
+
+        let mut chess960 = Game::new_chess960(0).unwrap();
+        for _ in 0..6 {
+            let m = chess960.legal_moves()[0];
+            chess960.apply_move(m).unwrap();
+        }
+        assert_same_game(&chess960);
+    }
+
+    #[test]
+    fn rejects_illegal_moves() {
+        let json = r#"{"start_fen":"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1","moves":["e2e5"]}"#;
+        assert!(serde_json::from_str::<Game>(json).is_err());
+    }
+
+    #[test]
+    fn figures() {
+        for color in [FigureColor::White, FigureColor::Black] {
+            assert_eq!(round_trip(&color), color);
+            for variant in [
+                FigureVariant::King,
+                FigureVariant::Queen,
+                FigureVariant::Rook,
+                FigureVariant::Bishop,
+                FigureVariant::Knight,
+                FigureVariant::Pawn,
+            ] {
+                assert_eq!(round_trip(&variant), variant);
+                let figure = Figure::new(color, variant);
+                assert_eq!(round_trip(&figure), figure);
+            }
+        }
+    }
+
+    #[test]
+    fn states_and_moves() {
+        for state in [
+            GameState::Play,
+            GameState::Check(FigureColor::White),
+            GameState::Checkmate(FigureColor::Black),
+            GameState::Stalemate(FigureColor::White),
+            GameState::OutOfTime(FigureColor::Black),
+        ] {
+            assert_eq!(round_trip(&state), state);
+        }
+        let mut game = Game::new();
+        game.lose_on_time(FigureColor::White);
+        assert_eq!(round_trip(&game).state(), game.state());
+        for m in ["e2e4", "e7e8q", "a2a1n", "e1g1"] {
+            let m: Move = m.parse().unwrap();
+            assert_eq!(round_trip(&m), m);
+        }
+        assert_eq!(
+            serde_json::to_string(&"e7e8q".parse::<Move>().unwrap()).unwrap(),
+            r#""e7e8q""#
+        );
+    }
+}
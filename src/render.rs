@@ -0,0 +1,500 @@
+use std::fmt::Display;
+
+use colored::{Colorize, CustomColor};
+
+use crate::{
+    eval::figure_value,
+    figure::{Figure, FigureColor},
+    game::{Game, GameState},
+    moves::Move,
+    square::{File, Rank, Square},
+};
+
+/// which side of the board is drawn at the bottom
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    White,
+    Black,
+    SideToMove,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub name: &'static str,
+    pub light: CustomColor,
+    pub dark: CustomColor,
+    pub last_move: CustomColor,
+    pub check: CustomColor,
+    pub selected: CustomColor,
+    pub target: CustomColor,
+    pub cursor: CustomColor,
+    pub hint: CustomColor,
+    /// draw letters and markers instead of colors, for terminals without color support
+    pub ascii: bool,
+}
+
+impl Theme {
+    pub const NAMES: [&'static str; 4] = ["classic", "wood", "green", "ascii"];
+
+    pub fn classic() -> Self {
+        Theme {
+            name: "classic",
+            light: CustomColor::new(204, 183, 174),
+            dark: CustomColor::new(112, 102, 119),
+            last_move: CustomColor::new(170, 162, 58),
+            check: CustomColor::new(200, 60, 60),
+            selected: CustomColor::new(100, 150, 200),
+            target: CustomColor::new(140, 190, 140),
+            cursor: CustomColor::new(120, 180, 220),
+            hint: CustomColor::new(220, 150, 80),
+            ascii: false,
+        }
+    }
+
+    pub fn wood() -> Self {
+        Theme {
+            name: "wood",
+            light: CustomColor::new(240, 217, 181),
+            dark: CustomColor::new(181, 136, 99),
+            last_move: CustomColor::new(205, 210, 106),
+            check: CustomColor::new(230, 80, 60),
+            selected: CustomColor::new(130, 151, 105),
+            target: CustomColor::new(170, 200, 120),
+            cursor: CustomColor::new(110, 160, 210),
+            hint: CustomColor::new(225, 120, 70),
+            ascii: false,
+        }
+    }
+
+    pub fn green() -> Self {
+        Theme {
+            name: "green",
+            light: CustomColor::new(238, 238, 210),
+            dark: CustomColor::new(118, 150, 86),
+            last_move: CustomColor::new(246, 246, 105),
+            check: CustomColor::new(220, 70, 70),
+            selected: CustomColor::new(186, 202, 68),
+            target: CustomColor::new(150, 190, 220),
+            cursor: CustomColor::new(100, 160, 230),
+            hint: CustomColor::new(240, 160, 60),
+            ascii: false,
+        }
+    }
+
+    pub fn ascii() -> Self {
+        Theme {
+            name: "ascii",
+            ascii: true,
+            ..Self::classic()
+        }
+    }
+
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "classic" => Some(Self::classic()),
+            "wood" => Some(Self::wood()),
+            "green" => Some(Self::green()),
+            "ascii" => Some(Self::ascii()),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn square_color(&self, square: Square) -> CustomColor {
+        if square.is_light() {
+            self.light
+        } else {
+            self.dark
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderOptions {
+    pub orientation: Orientation,
+    pub theme: Theme,
+    pub highlight_last_move: bool,
+    pub highlight_check: bool,
+    /// field of a figure whose legal targets get marked
+    pub selected: Option<Square>,
+    /// field under the keyboard cursor of an interactive front end
+    pub cursor: Option<Square>,
+    /// a move suggested to the player, both of its fields get marked
+    pub hint: Option<Move>,
+    /// the state, material, captured figures and moves in SAN next to the board,
+    /// off by default so that `Display for Game` stays a plain board
+    pub panel: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            orientation: Orientation::White,
+            theme: Theme::default(),
+            highlight_last_move: true,
+            highlight_check: true,
+            selected: None,
+            cursor: None,
+            hint: None,
+            panel: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Highlight {
+    None,
+    LastMove,
+    Target,
+    Selected,
+    Check,
+    Cursor,
+    Hint,
+}
+
+/// a [`Game`] drawn with the given [`RenderOptions`], see [`Game::render`]
+pub struct BoardView<'a> {
+    game: &'a Game,
+    options: &'a RenderOptions,
+}
+
+impl<'a> BoardView<'a> {
+    pub(crate) fn new(game: &'a Game, options: &'a RenderOptions) -> Self {
+        BoardView { game, options }
+    }
+
+    /// lines taken by the drawing, the board plus a row of file letters above and below
+    pub const HEIGHT: u16 = 10;
+    /// columns taken by the drawing without the panel, each field is three characters wide
+    pub const WIDTH: u16 = 28;
+
+    pub fn white_at_bottom(&self) -> bool {
+        match self.options.orientation {
+            Orientation::White => true,
+            Orientation::Black => false,
+            Orientation::SideToMove => self.game.side_to_move() == FigureColor::White,
+        }
+    }
+
+    fn files(&self) -> Vec<File> {
+        let mut files = File::ALL.to_vec();
+        if !self.white_at_bottom() {
+            files.reverse();
+        }
+        files
+    }
+
+    fn ranks(&self) -> Vec<Rank> {
+        let mut ranks: Vec<Rank> = Rank::ALL.into_iter().rev().collect();
+        if !self.white_at_bottom() {
+            ranks.reverse();
+        }
+        ranks
+    }
+
+    /// the field drawn at column `x` and line `y` of the drawing
+    pub fn square_at(&self, x: u16, y: u16) -> Option<Square> {
+        if !(2..26).contains(&x) || !(1..9).contains(&y) {
+            return None;
+        }
+        let file = self.files()[(x as usize - 2) / 3];
+        let rank = self.ranks()[y as usize - 1];
+        Some(Square::new(file, rank))
+    }
+
+    fn highlights(&self) -> Vec<(Square, Highlight)> {
+        let mut highlights = Vec::new();
+
+        if self.options.highlight_last_move {
+            if let Some(m) = self.game.last_move() {
+                highlights.push((m.from, Highlight::LastMove));
+                highlights.push((m.to, Highlight::LastMove));
+            }
+        }
+
+        if let Some(hint) = self.options.hint {
+            highlights.push((hint.from, Highlight::Hint));
+            highlights.push((hint.to, Highlight::Hint));
+        }
+
+        if let Some(selected) = self.options.selected {
+            for m in self
+                .game
+                .legal_moves()
+                .iter()
+                .filter(|m| m.from == selected)
+            {
+                highlights.push((m.to, Highlight::Target));
+            }
+            highlights.push((selected, Highlight::Selected));
+        }
+
+        if self.options.highlight_check {
+            for color in [FigureColor::White, FigureColor::Black] {
+                if self.game.is_in_check(color) {
+                    if let Some(king) = self.game.king_position(color) {
+                        highlights.push((king, Highlight::Check));
+                    }
+                }
+            }
+        }
+
+        if let Some(cursor) = self.options.cursor {
+            highlights.push((cursor, Highlight::Cursor));
+        }
+
+        highlights
+    }
+
+    /// the plain name of `color` for ascii drawings, the colored one otherwise
+    fn color_name(&self, color: FigureColor) -> String {
+        match self.options.theme.ascii {
+            true => color.name().to_string(),
+            false => color.to_string(),
+        }
+    }
+
+    /// the material balance and the figures each side took, one line each
+    pub fn material(&self) -> Vec<String> {
+        let game = self.game;
+        // in pawns, 1 for a pawn up to 9 for a queen
+        let balance: i32 = game
+            .board
+            .values()
+            .map(|f| match f.color {
+                FigureColor::White => figure_value(f.variant) / 100,
+                FigureColor::Black => -figure_value(f.variant) / 100,
+            })
+            .sum();
+        let mut lines = vec![match balance {
+            0 => "material even".to_string(),
+            b if b > 0 => format!("material white +{}", b),
+            b => format!("material black +{}", -b),
+        }];
+
+        let captured = game.captured();
+        for color in [FigureColor::White, FigureColor::Black] {
+            let taken: String = captured[color.index()]
+                .iter()
+                .map(|f| match self.options.theme.ascii {
+                    true => f.get_letter(),
+                    false => f.get_symbol(),
+                })
+                .collect();
+            lines.push(
+                format!("{} took {}", color.name(), taken)
+                    .trim_end()
+                    .to_string(),
+            );
+        }
+        lines
+    }
+
+    /// the moves so far in SAN, a numbered row for each move of white and black
+    pub fn move_rows(&self) -> Vec<String> {
+        let game = self.game;
+        // the FEN of the start tells who moved first and from which move on
+        let start = game.start_fen().split(' ').collect::<Vec<_>>();
+        let black_first = start.get(1) == Some(&"b");
+        let first_number: usize = start.get(5).and_then(|n| n.parse().ok()).unwrap_or(1);
+
+        let mut sans = game.san_history();
+        if black_first {
+            sans.insert(0, "...".to_string());
+        }
+        sans.chunks(2)
+            .enumerate()
+            .map(|(i, pair)| {
+                let black = pair.get(1).map_or("", String::as_str);
+                format!("{:>3}. {:<8}{}", first_number + i, pair[0], black)
+            })
+            .collect()
+    }
+
+    /// the lines next to the board, as many as the board is high
+    fn panel(&self) -> Vec<String> {
+        let game = self.game;
+        let mut lines = vec![match game.state() {
+            GameState::Play => format!("{} to move", self.color_name(game.side_to_move())),
+            GameState::Check(color) => format!("check, {} to move", self.color_name(color)),
+            GameState::Checkmate(color) => {
+                format!("checkmate, {} wins", self.color_name(color.opposite()))
+            }
+            GameState::Stalemate(_) => "stalemate, it's a draw".to_string(),
+            GameState::OutOfTime(color) => format!(
+                "{} ran out of time, {} wins",
+                self.color_name(color),
+                self.color_name(color.opposite())
+            ),
+        }];
+        lines.extend(self.material());
+
+        let rows = self.move_rows();
+        let room = Self::HEIGHT as usize - lines.len();
+        lines.extend(rows.iter().skip(rows.len().saturating_sub(room)).cloned());
+        lines.resize(Self::HEIGHT as usize, String::new());
+        lines
+    }
+
+    fn write_square(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        square: Square,
+        highlight: Highlight,
+    ) -> std::fmt::Result {
+        let figure = self.game.board.get(&square);
+        let theme = &self.options.theme;
+
+        if theme.ascii {
+            let symbol = figure.map(Figure::get_letter).unwrap_or(match highlight {
+                Highlight::Target => '*',
+                _ => '.',
+            });
+            let (left, right) = match highlight {
+                Highlight::None => (' ', ' '),
+                Highlight::Target if figure.is_none() => (' ', ' '),
+                Highlight::Target => ('<', '>'),
+                Highlight::LastMove => ('(', ')'),
+                Highlight::Selected => ('[', ']'),
+                Highlight::Check => ('!', '!'),
+                Highlight::Cursor => ('{', '}'),
+                Highlight::Hint => ('>', '<'),
+            };
+            return write!(f, "{}{}{}", left, symbol, right);
+        }
+
+        let bg = match highlight {
+            Highlight::None => theme.square_color(square),
+            Highlight::LastMove => theme.last_move,
+            Highlight::Target => theme.target,
+            Highlight::Selected => theme.selected,
+            Highlight::Check => theme.check,
+            Highlight::Cursor => theme.cursor,
+            Highlight::Hint => theme.hint,
+        };
+        let symbol = match figure {
+            Some(figure) => format!(" {} ", figure),
+            None if highlight == Highlight::Target => format!(" {} ", "·".black()),
+            None => "   ".to_string(),
+        };
+        write!(f, "{}", symbol.on_custom_color(bg))
+    }
+}
+
+impl Display for BoardView<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let files = self.files();
+        let highlights = self.highlights();
+        let panel = match self.options.panel {
+            true => self.panel(),
+            false => Vec::new(),
+        };
+        let mut panel = panel.iter();
+        let mut side = |f: &mut std::fmt::Formatter<'_>| match panel.next() {
+            Some(line) if !line.is_empty() => write!(f, "   {}", line),
+            _ => Ok(()),
+        };
+
+        write!(f, "  ")?;
+        for file_letter in &files {
+            write!(f, " {} ", file_letter)?;
+        }
+        side(f)?;
+        writeln!(f)?;
+
+        for rank in self.ranks() {
+            write!(f, "{} ", rank)?;
+
+            for file_letter in &files {
+                let square = Square::new(*file_letter, rank);
+                // later highlights win, so a king in check stays red even if it just moved
+                let highlight = highlights
+                    .iter()
+                    .rev()
+                    .find(|(c, _)| *c == square)
+                    .map_or(Highlight::None, |(_, h)| *h);
+                self.write_square(f, square, highlight)?;
+            }
+            write!(f, " {}", rank)?;
+            side(f)?;
+            writeln!(f)?;
+        }
+        write!(f, "  ")?;
+
+        for file_letter in &files {
+            write!(f, " {} ", file_letter)?;
+        }
+        side(f)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panel() {
+        let game = Game::from_pgn("1. e4 d5 2. exd5 Qxd5 3. Nc3 Qxg2 4. Bxg2").unwrap();
+        let options = RenderOptions {
+            theme: Theme::ascii(),
+            panel: true,
+            ..Default::default()
+        };
+        let view = game.render(&options);
+        assert_eq!(
+            view.material(),
+            ["material white +8", "white took qp", "black took PP"]
+        );
+        assert_eq!(
+            view.move_rows(),
+            [
+                "  1. e4      d5",
+                "  2. exd5    Qxd5",
+                "  3. Nc3     Qxg2",
+                "  4. Bxg2    "
+            ]
+        );
+        // nothing but plain text for terminals without colors
+        assert!(!view.to_string().contains('\x1b'));
+        assert!(view.to_string().contains("black to move"));
+
+        // the plain board unless asked for
+        assert!(!game.to_string().contains("black to move"));
+        assert!(!game.to_string().contains("Bxg2"));
+    }
+
+    #[test]
+    fn captures_follow_the_moves() {
+        let mut game = Game::from_fen("r3k3/1P6/8/8/4p3/8/3P4/4K3 w q - 0 1").unwrap();
+        let letters =
+            |figures: &[Figure]| figures.iter().map(Figure::get_letter).collect::<String>();
+        // en passant first, then a promotion taking the rook
+        for m in ["d2d4", "e4d3", "b7a8q", "e8d7", "e1d2", "d7e6", "d2d3"] {
+            game.apply_move(m.parse().unwrap()).unwrap();
+        }
+        let [white, black] = game.captured();
+        assert_eq!(letters(&white), "rp");
+        assert_eq!(letters(&black), "P");
+        assert_eq!(game.clone().captured(), [white, black]);
+    }
+
+    #[test]
+    fn moves_from_black() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 30";
+        let mut game = Game::from_fen(fen).unwrap();
+        game.apply_move("e8d8".parse().unwrap()).unwrap();
+        game.apply_move("e2e4".parse().unwrap()).unwrap();
+        let options = RenderOptions::default();
+        assert_eq!(
+            game.render(&options).move_rows(),
+            [" 30. ...     Kd8", " 31. e4      "]
+        );
+    }
+}
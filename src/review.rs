@@ -0,0 +1,342 @@
+use std::{fmt::Display, sync::atomic::AtomicBool};
+
+use crate::{
+    engine::{Engine, MATE, MATE_BOUND},
+    figure::FigureColor,
+    game::{Game, GameState},
+    moves::Move,
+};
+
+/// scores are capped here when comparing moves, losing a won position counts as much as
+/// dropping a rook, being mated in 3 instead of 5 does not count at all
+const DECIDED: i32 = 1000;
+
+/// how much worse than the best move a move was
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Judgement {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Judgement {
+    pub const ALL: [Judgement; 3] = [
+        Judgement::Inaccuracy,
+        Judgement::Mistake,
+        Judgement::Blunder,
+    ];
+
+    /// the judgement of a move losing `loss` centipawns, `None` for good enough moves
+    pub fn from_loss(loss: i32) -> Option<Self> {
+        match loss {
+            300.. => Some(Judgement::Blunder),
+            100.. => Some(Judgement::Mistake),
+            50.. => Some(Judgement::Inaccuracy),
+            _ => None,
+        }
+    }
+
+    /// the PGN annotation, `?!`, `?` or `??`
+    pub fn glyph(&self) -> &'static str {
+        match self {
+            Judgement::Inaccuracy => "?!",
+            Judgement::Mistake => "?",
+            Judgement::Blunder => "??",
+        }
+    }
+}
+
+impl Display for Judgement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Judgement::Inaccuracy => "inaccuracy",
+            Judgement::Mistake => "mistake",
+            Judgement::Blunder => "blunder",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// the engine's opinion of one move of a game, scores are for the side that played it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveReview {
+    pub played: Move,
+    pub played_score: i32,
+    pub best_score: i32,
+    /// what the engine would have played instead, its best move and the expected replies
+    pub best_line: Vec<Move>,
+}
+
+impl MoveReview {
+    /// centipawns the played move gave away compared to the best one
+    pub fn loss(&self) -> i32 {
+        let capped = |score: i32| score.clamp(-DECIDED, DECIDED);
+        (capped(self.best_score) - capped(self.played_score)).max(0)
+    }
+
+    pub fn judgement(&self) -> Option<Judgement> {
+        Judgement::from_loss(self.loss())
+    }
+}
+
+/// a game with every move looked at by the engine, see [`Engine::review`]
+#[derive(Debug, Clone)]
+pub struct Review {
+    game: Game,
+    depth: u32,
+    moves: Vec<MoveReview>,
+}
+
+impl Review {
+    pub fn moves(&self) -> &[MoveReview] {
+        &self.moves
+    }
+
+    /// how many moves of `color` got the judgement
+    pub fn count(&self, color: FigureColor, judgement: Judgement) -> usize {
+        let start = Game::from_fen(self.game.start_fen()).unwrap_or_default();
+        self.moves
+            .iter()
+            .enumerate()
+            .filter(|(i, review)| {
+                let mover = match i % 2 {
+                    0 => start.side_to_move(),
+                    _ => start.side_to_move().opposite(),
+                };
+                mover == color && review.judgement() == Some(judgement)
+            })
+            .count()
+    }
+
+    /// the game as PGN with the evaluation after every move, `?!`, `?` and `??` on
+    /// inaccuracies, mistakes and blunders, and the engine's line as a variation for them
+    pub fn to_pgn(&self) -> String {
+        let mut position = Game::from_fen(self.game.start_fen()).unwrap_or_default();
+        position.chess960 = self.game.is_chess960();
+        let mut annotations = Vec::new();
+
+        for review in &self.moves {
+            let mover = position.side_to_move();
+            let before = position.clone();
+            position.play(review.played);
+
+            let mut annotation = Vec::new();
+            let judgement = review.judgement();
+            if let Some(judgement) = judgement {
+                annotation.push(judgement.glyph().to_string());
+            }
+            if !position.is_over() {
+                let white_score = match mover {
+                    FigureColor::White => review.played_score,
+                    FigureColor::Black => -review.played_score,
+                };
+                annotation.push("{".to_string());
+                annotation.push(format!("[%eval {}]", format_eval(white_score)));
+                if let (Some(judgement), Some(best)) = (judgement, review.best_line.first()) {
+                    annotation.push(format!("{},", judgement));
+                    annotation.push(before.san(best));
+                    annotation.push("was".to_string());
+                    annotation.push("best".to_string());
+                }
+                annotation.push("}".to_string());
+            }
+            if judgement.is_some() && !review.best_line.is_empty() {
+                let mut line = numbered_line(&before, &review.best_line);
+                line[0].insert(0, '(');
+                if let Some(last) = line.last_mut() {
+                    last.push(')');
+                }
+                annotation.extend(line);
+            }
+            annotations.push(annotation);
+        }
+
+        let annotator = format!("chess minimax depth {}", self.depth);
+        self.game
+            .annotated_pgn(&[("Annotator", &annotator)], &annotations)
+    }
+}
+
+/// the moves in SAN with move numbers, starting with `12...` when black moves first
+fn numbered_line(game: &Game, moves: &[Move]) -> Vec<String> {
+    let mut number = game.turns() / 2 + 1;
+    let mut white = game.side_to_move() == FigureColor::White;
+    let mut tokens = Vec::new();
+
+    for (i, san) in game.san_line(moves).into_iter().enumerate() {
+        if white {
+            tokens.push(format!("{}.", number));
+        } else if i == 0 {
+            tokens.push(format!("{}...", number));
+        }
+        if !white {
+            number += 1;
+        }
+        white = !white;
+        tokens.push(san);
+    }
+    tokens
+}
+
+/// a score for white the way PGN `%eval` comments write it, pawns like `-1.25` or a mate like `#3`
+fn format_eval(score: i32) -> String {
+    if score.abs() > MATE_BOUND {
+        // the score counts half-moves from before the move, the comment moves after it
+        let moves = (MATE - score.abs()) / 2;
+        format!("#{}", moves * score.signum())
+    } else {
+        format!("{:.2}", score as f64 / 100.0)
+    }
+}
+
+impl Engine {
+    /// searches every position of the game to the engine's depth and compares the move
+    /// played with the best one, the book is not used
+    pub fn review(&self, game: &Game) -> Review {
+        let never = AtomicBool::new(false);
+        let mut position = Game::from_fen(game.start_fen()).unwrap_or_default();
+        position.chess960 = game.is_chess960();
+        let mut moves = Vec::new();
+
+        for &played in game.history() {
+            let Some(best) = self.analyze(&position, self.depth(), &never, |_| ()) else {
+                break;
+            };
+            let mut after = position.clone();
+            after.play(played);
+
+            let played_score = if best.best_move() == Some(played) {
+                best.score
+            } else {
+                match after.state() {
+                    GameState::Checkmate(_) => MATE - 1,
+                    GameState::Stalemate(_) => 0,
+                    _ if self.depth() == 1 => -after.evaluate().relative(after.side_to_move()),
+                    _ => {
+                        // the reply is searched one half-move less, so both end at the same depth
+                        let reply = self.analyze(&after, self.depth() - 1, &never, |_| ());
+                        reply.map_or(0, |reply| match reply.mate_in() {
+                            // seen from before the move, a mate is one more half-move away
+                            Some(_) => -reply.score + reply.score.signum(),
+                            None => -reply.score,
+                        })
+                    }
+                }
+            };
+
+            moves.push(MoveReview {
+                played,
+                played_score,
+                best_score: best.score,
+                best_line: best.pv,
+            });
+            position = after;
+        }
+
+        Review {
+            game: game.clone(),
+            depth: self.depth(),
+            moves,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(moves: &[&str]) -> Game {
+        let mut game = Game::new();
+        for san in moves {
+            let m = game.parse_san(san).unwrap();
+            game.apply_move(m).unwrap();
+        }
+        game
+    }
+
+    fn review(played: &str, played_score: i32, best_score: i32, best_line: &[&str]) -> MoveReview {
+        MoveReview {
+            played: played.parse().unwrap(),
+            played_score,
+            best_score,
+            best_line: best_line.iter().map(|m| m.parse().unwrap()).collect(),
+        }
+    }
+
+    #[test]
+    fn thresholds() {
+        assert_eq!(Judgement::from_loss(0), None);
+        assert_eq!(Judgement::from_loss(49), None);
+        assert_eq!(Judgement::from_loss(50), Some(Judgement::Inaccuracy));
+        assert_eq!(Judgement::from_loss(99), Some(Judgement::Inaccuracy));
+        assert_eq!(Judgement::from_loss(100), Some(Judgement::Mistake));
+        assert_eq!(Judgement::from_loss(299), Some(Judgement::Mistake));
+        assert_eq!(Judgement::from_loss(300), Some(Judgement::Blunder));
+        let glyphs: Vec<_> = Judgement::ALL.iter().map(Judgement::glyph).collect();
+        assert_eq!(glyphs, ["?!", "?", "??"]);
+
+        // a better move than the engine's is not a negative loss
+        assert_eq!(review("e2e4", 80, 20, &[]).loss(), 0);
+        // mates count as much as a decided position
+        assert_eq!(review("e2e4", -MATE + 3, MATE - 5, &[]).loss(), 2 * DECIDED);
+        assert_eq!(review("e2e4", MATE - 5, MATE - 3, &[]).judgement(), None);
+    }
+
+    #[test]
+    fn annotations() {
+        let review = Review {
+            game: game(&["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6", "Qxf7#"]),
+            depth: 2,
+            moves: vec![
+                review("e2e4", 30, 30, &["e2e4", "e7e5"]),
+                review("e7e5", -30, -30, &["e7e5"]),
+                review("d1h5", -40, 30, &["g1f3"]),
+                review("b8c6", -80, -80, &["b8c6"]),
+                review("f1c4", 80, 80, &["f1c4"]),
+                review("g8f6", -MATE + 2, -90, &["g7g6", "h5f3"]),
+                review("h5f7", MATE - 1, MATE - 1, &["h5f7"]),
+            ],
+        };
+        // the moves get wrapped into lines, compare them as one
+        let pgn = review
+            .to_pgn()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert!(pgn.contains("[Annotator \"chess minimax depth 2\"]"));
+        assert!(
+            pgn.contains("1. e4 { [%eval 0.30] } 1... e5 { [%eval 0.30] }"),
+            "{}",
+            pgn
+        );
+        assert!(
+            pgn.contains("2. Qh5?! { [%eval -0.40] inaccuracy, Nf3 was best } (2. Nf3) 2... Nc6"),
+            "{}",
+            pgn
+        );
+        assert!(
+            pgn.contains(
+                "3... Nf6?? { [%eval #1] blunder, g6 was best } (3... g6 4. Qf3) 4. Qxf7#"
+            ),
+            "{}",
+            pgn
+        );
+        // no comment once the game is over
+        assert!(pgn.contains("4. Qxf7# 1-0"), "{}", pgn);
+        assert_eq!(review.count(FigureColor::White, Judgement::Inaccuracy), 1);
+        assert_eq!(review.count(FigureColor::Black, Judgement::Blunder), 1);
+        assert_eq!(review.count(FigureColor::White, Judgement::Blunder), 0);
+    }
+
+    #[test]
+    fn engine_finds_the_blunder() {
+        let game = game(&["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6", "Qxf7#"]);
+        let review = Engine::new(2).review(&game);
+        assert_eq!(review.moves().len(), 7);
+        let blunder = &review.moves()[5];
+        assert_eq!(blunder.judgement(), Some(Judgement::Blunder));
+        assert!(blunder.played_score < -MATE_BOUND);
+        assert_ne!(blunder.best_line.first(), Some(&blunder.played));
+        assert_eq!(review.moves()[6].played_score, MATE - 1);
+    }
+}
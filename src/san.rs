@@ -0,0 +1,251 @@
+use crate::{
+    figure::FigureVariant,
+    game::{Game, GameState},
+    moves::Move,
+    square::{File, Rank, Square},
+};
+
+impl Game {
+    /// writes a legal move in Standard Algebraic Notation, eg `Nbd2`, `exd6`, `O-O` or `e8=Q#`
+    pub fn san(&self, m: &Move) -> String {
+        let Some(figure) = self.board.get(&m.from) else {
+            return m.to_string();
+        };
+
+        let mut san = match self.castling_side(m) {
+            Some(true) => "O-O".to_string(),
+            Some(false) => "O-O-O".to_string(),
+            None => {
+                let takes = self.board.contains_key(&m.to)
+                    || (figure.variant == FigureVariant::Pawn && m.from.file() != m.to.file());
+                let mut san = String::new();
+
+                if figure.variant == FigureVariant::Pawn {
+                    if takes {
+                        san.push(m.from.file().to_char());
+                    }
+                } else {
+                    san.push(figure.variant.to_char().to_ascii_uppercase());
+
+                    let others: Vec<Move> = self
+                        .legal_moves()
+                        .into_iter()
+                        .filter(|o| o.to == m.to && o.from != m.from)
+                        .filter(|o| {
+                            self.board.get(&o.from).map(|f| f.variant) == Some(figure.variant)
+                        })
+                        .collect();
+                    if !others.is_empty() {
+                        if others.iter().all(|o| o.from.file() != m.from.file()) {
+                            san.push(m.from.file().to_char());
+                        } else if others.iter().all(|o| o.from.rank() != m.from.rank()) {
+                            san.push(m.from.rank().to_char());
+                        } else {
+                            san.push_str(&m.from.to_string());
+                        }
+                    }
+                }
+
+                if takes {
+                    san.push('x');
+                }
+                san.push_str(&m.to.to_string());
+
+                if let Some(promotion) = m.promotion {
+                    san.push('=');
+                    san.push(promotion.to_char().to_ascii_uppercase());
+                }
+                san
+            }
+        };
+
+        let mut after = self.clone();
+        if after.apply_move(*m).is_ok() {
+            match after.state() {
+                GameState::Checkmate(_) => san.push('#'),
+                GameState::Check(_) => san.push('+'),
+                _ => (),
+            }
+        }
+
+        san
+    }
+
+    /// finds the legal move written in Standard Algebraic Notation, annotations like
+    /// `+`, `#`, `!` or `?` are ignored
+    pub fn parse_san(&self, san: &str) -> Result<Move, &'static str> {
+        let san = san.trim().trim_end_matches(['+', '#', '!', '?']);
+        let legal = self.legal_moves();
+
+        let castling = match san {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(kingside) = castling {
+            return legal
+                .into_iter()
+                .find(|m| self.castling_side(m) == Some(kingside))
+                .ok_or("castling is not possible now");
+        }
+
+        let (san, promotion) = match san.split_once('=') {
+            Some((san, promotion)) => (san, Some(promotion)),
+            None => match san.char_indices().last() {
+                Some((i, c)) if c.is_ascii_uppercase() && i > 0 => (&san[..i], Some(&san[i..])),
+                _ => (san, None),
+            },
+        };
+        let promotion = match promotion {
+            Some(p) => Some(
+                p.chars()
+                    .next()
+                    .and_then(FigureVariant::from_char)
+                    .filter(|v| FigureVariant::PROMOTIONS.contains(v))
+                    .ok_or("unable to parse the promotion")?,
+            ),
+            None => None,
+        };
+
+        let (variant, rest) = match san.chars().next() {
+            Some(c) if c.is_ascii_uppercase() => (
+                FigureVariant::from_char(c).ok_or("unknown figure letter")?,
+                &san[1..],
+            ),
+            _ => (FigureVariant::Pawn, san),
+        };
+
+        let rest = rest.replace('x', "");
+        if rest.len() < 2 || !rest.is_ascii() {
+            return Err("unable to parse the move");
+        }
+        let (hint, target) = rest.split_at(rest.len() - 2);
+        let target: Square = target.parse()?;
+
+        let candidates: Vec<Move> = legal
+            .into_iter()
+            .filter(|m| m.to == target && self.castling_side(m).is_none())
+            .filter(|m| self.board.get(&m.from).map(|f| f.variant) == Some(variant))
+            .filter(|m| match m.promotion {
+                Some(_) => m.promotion == promotion.or(Some(FigureVariant::Queen)),
+                None => promotion.is_none(),
+            })
+            .filter(|m| {
+                hint.chars().all(|c| match c {
+                    'a'..='h' => File::from_char(c) == Some(m.from.file()),
+                    '1'..='8' => Rank::from_char(c) == Some(m.from.rank()),
+                    _ => false,
+                })
+            })
+            .collect();
+
+        match candidates.len() {
+            1 => Ok(candidates[0]),
+            0 => Err("no legal move matches that"),
+            _ => Err("the move is ambiguous"),
+        }
+    }
+
+    /// a line of moves played from the current position in Standard Algebraic Notation,
+    /// it stops at the first move that is not legal
+    pub fn san_line(&self, moves: &[Move]) -> Vec<String> {
+        let mut position = self.clone();
+        let mut sans = Vec::new();
+        for m in moves {
+            let san = position.san(m);
+            if position.apply_move(*m).is_err() {
+                break;
+            }
+            sans.push(san);
+        }
+        sans
+    }
+
+    /// the moves played so far in Standard Algebraic Notation
+    pub fn san_history(&self) -> Vec<String> {
+        let Ok(mut replay) = Game::from_fen(self.start_fen()) else {
+            return self.history().iter().map(|m| m.to_string()).collect();
+        };
+        replay.chess960 = self.chess960;
+
+        let mut sans = Vec::new();
+        for m in self.history() {
+            sans.push(replay.san(m));
+            if replay.apply_move(*m).is_err() {
+                break;
+            }
+        }
+        sans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn san_of(fen: &str, m: &str) -> String {
+        Game::from_fen(fen).unwrap().san(&m.parse().unwrap())
+    }
+
+    #[test]
+    fn disambiguation() {
+        let knights = "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1";
+        assert_eq!(san_of(knights, "b1d2"), "Nbd2");
+        assert_eq!(san_of(knights, "f1d2"), "Nfd2");
+        assert_eq!(san_of(knights, "b1c3"), "Nc3");
+
+        let rooks = "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1";
+        assert_eq!(san_of(rooks, "a1a3"), "R1a3");
+        assert_eq!(san_of(rooks, "a5a3"), "R5a3");
+
+        let queens = "2k5/8/8/8/4Q2Q/8/8/K6Q w - - 0 1";
+        assert_eq!(san_of(queens, "h4e1"), "Qh4e1");
+        assert_eq!(san_of(queens, "e4e1"), "Qee1");
+        assert_eq!(san_of(queens, "h1e1"), "Q1e1");
+    }
+
+    #[test]
+    fn special_moves() {
+        let castling = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(san_of(castling, "e1g1"), "O-O");
+        assert_eq!(san_of(castling, "e1c1"), "O-O-O");
+        assert_eq!(san_of(castling, "a1a8"), "Rxa8+");
+
+        let en_passant = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        assert_eq!(san_of(en_passant, "e5d6"), "exd6");
+
+        let promotion = "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(san_of(promotion, "b7b8q"), "b8=Q+");
+        assert_eq!(san_of(promotion, "b7b8n"), "b8=N");
+
+        let mate = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+        assert_eq!(san_of(mate, "a1a8"), "Ra8#");
+    }
+
+    #[test]
+    fn parses_what_it_writes() {
+        for fen in [
+            crate::fen::STARTING_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 b kq - 0 1",
+            "2k5/8/8/8/4Q2Q/8/8/K6Q w - - 0 1",
+        ] {
+            let game = Game::from_fen(fen).unwrap();
+            for m in game.legal_moves() {
+                assert_eq!(game.parse_san(&game.san(&m)), Ok(m), "{} in {}", m, fen);
+            }
+        }
+    }
+
+    #[test]
+    fn reads_loose_san() {
+        let game = Game::from_fen("4k3/1P6/8/8/8/8/8/1N2KN2 w - - 0 1").unwrap();
+        assert_eq!(game.parse_san("b8Q"), Ok("b7b8q".parse().unwrap()));
+        assert_eq!(game.parse_san("b8=N!?"), Ok("b7b8n".parse().unwrap()));
+        assert_eq!(game.parse_san("Nb1d2"), Ok("b1d2".parse().unwrap()));
+        assert_eq!(game.parse_san("Nd2"), Err("the move is ambiguous"));
+        assert_eq!(game.parse_san("Nd4"), Err("no legal move matches that"));
+        assert!(game.parse_san("O-O").is_err());
+        assert!(game.parse_san("Zé4").is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use crate::{
+    fen::{needs_chess960, parse_castling},
+    figure::{Figure, FigureColor},
+    game::{CastlingRights, Game},
+    square::Square,
+    validate::ValidationError,
+};
+
+/// a position being put together figure by figure before play starts
+#[derive(Debug, Clone)]
+pub struct Setup {
+    pub board: HashMap<Square, Figure>,
+    pub side_to_move: FigureColor,
+    pub castling: CastlingRights,
+    pub en_passant: Option<Square>,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+    /// castling follows the Chess960 rules even when the king and rooks stand where they do in standard chess
+    pub chess960: bool,
+}
+
+impl Setup {
+    pub fn empty() -> Self {
+        Setup {
+            board: HashMap::new(),
+            side_to_move: FigureColor::White,
+            castling: CastlingRights::none(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            chess960: false,
+        }
+    }
+
+    /// starts from the current position of `game`
+    pub fn from_game(game: &Game) -> Self {
+        Setup {
+            board: game.board.clone(),
+            side_to_move: game.side_to_move(),
+            castling: game.castling_rights(),
+            en_passant: game.en_passant(),
+            halfmove_clock: game.halfmove_clock(),
+            fullmove_number: game.turns() / 2 + 1,
+            chess960: game.is_chess960(),
+        }
+    }
+
+    pub fn place(&mut self, square: Square, figure: Figure) {
+        self.board.insert(square, figure);
+    }
+
+    pub fn remove(&mut self, square: Square) -> Option<Figure> {
+        self.board.remove(&square)
+    }
+
+    pub fn clear(&mut self) {
+        self.board.clear();
+        self.castling = CastlingRights::none();
+        self.en_passant = None;
+    }
+
+    /// sets the castling rights from a FEN castling field like `KQkq`, `HAha` or `-`
+    pub fn set_castling(&mut self, field: &str) -> Result<(), &'static str> {
+        self.castling = parse_castling(field, &self.board)?;
+        Ok(())
+    }
+
+    /// the position as a game without checking it, eg for drawing it while editing
+    pub fn preview(&self) -> Game {
+        let turns = (self.fullmove_number.max(1) - 1)
+            .saturating_mul(2)
+            .saturating_add((self.side_to_move == FigureColor::Black) as u32);
+        Game::from_parts(
+            self.board.clone(),
+            self.side_to_move,
+            self.castling,
+            self.en_passant,
+            self.halfmove_clock,
+            turns,
+            self.chess960 || needs_chess960(&self.board, &self.castling),
+        )
+    }
+
+    /// checks that the position can be played, see [`Game::validate`]
+    pub fn check(&self) -> Result<(), Vec<ValidationError>> {
+        self.preview().validate()
+    }
+
+    /// the game starting from this position, once it passes [`Setup::check`]
+    pub fn into_game(self) -> Result<Game, Vec<ValidationError>> {
+        let game = self.preview();
+        game.validate()?;
+        Ok(game)
+    }
+}
+
+impl Default for Setup {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::figure::FigureVariant;
+
+    #[test]
+    fn from_game_keeps_the_counters_and_rules() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 7 31";
+        let setup = Setup::from_game(&Game::from_fen(fen).unwrap());
+        assert_eq!((setup.halfmove_clock, setup.fullmove_number), (7, 31));
+        assert_eq!(setup.preview().to_fen(), fen);
+        assert_eq!(setup.into_game().unwrap().turns(), 61);
+
+        let chess960 = Game::new_chess960(518).unwrap();
+        let setup = Setup::from_game(&chess960);
+        assert!(setup.chess960);
+        assert!(setup.preview().is_chess960());
+        assert_eq!(setup.preview().to_fen(), chess960.to_fen());
+    }
+
+    fn square(s: &str) -> Square {
+        s.parse().unwrap()
+    }
+
+    fn kings() -> Setup {
+        let mut setup = Setup::empty();
+        setup.place(
+            square("e1"),
+            Figure::new(FigureColor::White, FigureVariant::King),
+        );
+        setup.place(
+            square("e8"),
+            Figure::new(FigureColor::Black, FigureVariant::King),
+        );
+        setup
+    }
+
+    #[test]
+    fn placing_and_removing() {
+        let mut setup = kings();
+        let rook = Figure::new(FigureColor::White, FigureVariant::Rook);
+        setup.place(square("h1"), rook);
+        assert_eq!(setup.preview().to_fen(), "4k3/8/8/8/8/8/8/4K2R w - - 0 1");
+        // placing on an occupied field replaces the figure
+        setup.place(
+            square("h1"),
+            Figure::new(FigureColor::Black, FigureVariant::Knight),
+        );
+        assert_eq!(
+            setup.remove(square("h1")).map(|f| f.color),
+            Some(FigureColor::Black)
+        );
+        assert_eq!(setup.remove(square("h1")), None);
+        assert_eq!(setup.check(), Ok(()));
+
+        setup.place(square("h1"), rook);
+        setup.set_castling("K").unwrap();
+        setup.en_passant = Some(square("d6"));
+        setup.clear();
+        assert!(setup.board.is_empty());
+        assert_eq!(setup.castling, CastlingRights::none());
+        assert_eq!(setup.en_passant, None);
+    }
+
+    #[test]
+    fn side_and_castling() {
+        let mut setup = Setup::from_game(&Game::new());
+        setup.side_to_move = FigureColor::Black;
+        setup.set_castling("Kq").unwrap();
+        let game = setup.clone().into_game().unwrap();
+        assert_eq!(game.side_to_move(), FigureColor::Black);
+        assert_eq!(
+            game.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b Kq - 0 1"
+        );
+        setup.set_castling("-").unwrap();
+        assert_eq!(setup.castling, CastlingRights::none());
+
+        // no rook left to castle with
+        setup.remove(square("h1"));
+        assert!(setup.set_castling("K").is_err());
+        assert!(setup.set_castling("x").is_err());
+    }
+
+    #[test]
+    fn rejections() {
+        let mut setup = kings();
+        setup.remove(square("e8"));
+        assert_eq!(
+            setup.check(),
+            Err(vec![ValidationError::MissingKing(FigureColor::Black)])
+        );
+        setup.place(
+            square("a8"),
+            Figure::new(FigureColor::Black, FigureVariant::King),
+        );
+        setup.place(
+            square("h8"),
+            Figure::new(FigureColor::Black, FigureVariant::King),
+        );
+        assert_eq!(
+            setup.check(),
+            Err(vec![ValidationError::ExtraKings(FigureColor::Black)])
+        );
+
+        let mut setup = kings();
+        setup.place(
+            square("c1"),
+            Figure::new(FigureColor::White, FigureVariant::Pawn),
+        );
+        assert_eq!(
+            setup.clone().into_game().err(),
+            Some(vec![ValidationError::PawnOnBackRank(square("c1"))])
+        );
+
+        let mut setup = kings();
+        setup.place(
+            square("e2"),
+            Figure::new(FigureColor::White, FigureVariant::Queen),
+        );
+        setup.side_to_move = FigureColor::White;
+        assert_eq!(
+            setup.check(),
+            Err(vec![ValidationError::OpponentInCheck(FigureColor::Black)])
+        );
+        setup.side_to_move = FigureColor::Black;
+        assert_eq!(setup.check(), Ok(()));
+    }
+}
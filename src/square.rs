@@ -0,0 +1,275 @@
+use std::{fmt::Display, str::FromStr};
+
+/// a column of the board, `a` to `h`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "char", try_from = "char")
+)]
+pub struct File(u8);
+
+/// a row of the board, `1` to `8`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Rank(u8);
+
+/// a field of the board, a1 is 0, b1 is 1 and h8 is 63
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct Square(u8);
+
+impl File {
+    pub const ALL: [File; 8] = [
+        File(0),
+        File(1),
+        File(2),
+        File(3),
+        File(4),
+        File(5),
+        File(6),
+        File(7),
+    ];
+    pub const A: File = File(0);
+    pub const C: File = File(2);
+    pub const D: File = File(3);
+    pub const E: File = File(4);
+    pub const F: File = File(5);
+    pub const G: File = File(6);
+    pub const H: File = File(7);
+
+    /// the file counted from 0 for `a`, `None` past `h`
+    pub fn new(index: u8) -> Option<Self> {
+        (index < 8).then_some(File(index))
+    }
+
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'a'..='h' => Some(File(c as u8 - b'a')),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        (b'a' + self.0) as char
+    }
+
+    pub fn index(self) -> u8 {
+        self.0
+    }
+
+    pub fn offset(self, diff: i8) -> Option<Self> {
+        u8::try_from(self.0 as i8 + diff).ok().and_then(File::new)
+    }
+
+    pub fn distance(self, other: File) -> u8 {
+        self.0.abs_diff(other.0)
+    }
+}
+
+impl Rank {
+    pub const ALL: [Rank; 8] = [
+        Rank(0),
+        Rank(1),
+        Rank(2),
+        Rank(3),
+        Rank(4),
+        Rank(5),
+        Rank(6),
+        Rank(7),
+    ];
+    pub const FIRST: Rank = Rank(0);
+    pub const EIGHTH: Rank = Rank(7);
+
+    /// the rank counted from 0 for the first one, `None` past the eighth
+    pub fn new(index: u8) -> Option<Self> {
+        (index < 8).then_some(Rank(index))
+    }
+
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '1'..='8' => Some(Rank(c as u8 - b'1')),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        (b'1' + self.0) as char
+    }
+
+    pub fn index(self) -> u8 {
+        self.0
+    }
+
+    /// the rank as written in notation, 1 to 8
+    pub fn number(self) -> u8 {
+        self.0 + 1
+    }
+
+    pub fn offset(self, diff: i8) -> Option<Self> {
+        u8::try_from(self.0 as i8 + diff).ok().and_then(Rank::new)
+    }
+
+    pub fn distance(self, other: Rank) -> u8 {
+        self.0.abs_diff(other.0)
+    }
+}
+
+impl Square {
+    pub fn new(file: File, rank: Rank) -> Self {
+        Square(rank.0 * 8 + file.0)
+    }
+
+    /// the square with the given index, `None` past 63
+    pub fn from_index(index: u8) -> Option<Self> {
+        (index < 64).then_some(Square(index))
+    }
+
+    pub fn index(self) -> u8 {
+        self.0
+    }
+
+    pub fn file(self) -> File {
+        File(self.0 % 8)
+    }
+
+    pub fn rank(self) -> Rank {
+        Rank(self.0 / 8)
+    }
+
+    /// shifts the square by the given number of files and ranks, `None` when that falls off the board
+    pub fn offset(self, x_diff: i8, y_diff: i8) -> Option<Self> {
+        Some(Square::new(
+            self.file().offset(x_diff)?,
+            self.rank().offset(y_diff)?,
+        ))
+    }
+
+    /// every square of the board, from a1 to h8
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..64).map(Square)
+    }
+
+    /// a1 is dark, its neighbours are light
+    pub fn is_light(self) -> bool {
+        (self.file().0 + self.rank().0) % 2 == 1
+    }
+}
+
+impl Display for File {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl Display for Rank {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl Display for Square {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.file(), self.rank())
+    }
+}
+
+impl FromStr for Square {
+    type Err = &'static str;
+
+    /// reads a field like "e4"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.trim().chars();
+
+        let file = chars
+            .next()
+            .and_then(|c| File::from_char(c.to_ascii_lowercase()))
+            .ok_or("file of a field has to be a letter from a to h")?;
+        let rank = chars
+            .next()
+            .and_then(Rank::from_char)
+            .ok_or("rank of a field has to be a number from 1 to 8")?;
+
+        if chars.next().is_some() {
+            return Err("a field is a letter and a number, like e4");
+        }
+
+        Ok(Square::new(file, rank))
+    }
+}
+
+impl From<File> for char {
+    fn from(file: File) -> Self {
+        file.to_char()
+    }
+}
+
+impl TryFrom<char> for File {
+    type Error = &'static str;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        File::from_char(c).ok_or("file has to be a letter from a to h")
+    }
+}
+
+impl From<Square> for String {
+    fn from(square: Square) -> Self {
+        square.to_string()
+    }
+}
+
+impl TryFrom<String> for Square {
+    type Error = &'static str;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_squares_off_the_board() {
+        for s in ["z9", "a0", "i1", "a9", "", "e", "e44", "4e"] {
+            assert!(s.parse::<Square>().is_err(), "{}", s);
+        }
+        assert_eq!(Square::from_index(64), None);
+        assert_eq!(File::new(8), None);
+        assert_eq!(Rank::new(8), None);
+        assert_eq!(Rank::from_char('0'), None);
+        assert_eq!("h8".parse::<Square>().unwrap().offset(1, 0), None);
+        assert_eq!("a1".parse::<Square>().unwrap().offset(0, -1), None);
+    }
+
+    #[test]
+    fn algebraic_round_trip() {
+        for square in Square::all() {
+            assert_eq!(square.to_string().parse::<Square>(), Ok(square));
+            assert_eq!(Square::from_index(square.index()), Some(square));
+            assert_eq!(Square::new(square.file(), square.rank()), square);
+        }
+        let e4: Square = "E4".parse().unwrap();
+        assert_eq!(e4.to_string(), "e4");
+        assert_eq!(e4.index(), 28);
+        assert_eq!(e4.rank().number(), 4);
+        assert_eq!(e4.offset(1, 2).unwrap().to_string(), "f6");
+    }
+
+    #[test]
+    fn colors() {
+        let light = |s: &str| s.parse::<Square>().unwrap().is_light();
+        assert!(!light("a1"));
+        assert!(!light("h8"));
+        assert!(!light("e5"));
+        assert!(light("h1"));
+        assert!(light("a8"));
+        assert!(light("b1"));
+        assert!(light("d1"));
+        assert_eq!(Square::all().filter(|s| s.is_light()).count(), 32);
+    }
+}
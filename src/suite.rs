@@ -0,0 +1,143 @@
+use std::{fs, sync::atomic::AtomicBool, time::Duration};
+
+use chess::{Engine, Epd};
+use colored::Colorize;
+
+const USAGE: &str = "usage: epd FILE [--movetime MS] [--depth N]";
+
+/// the deepest any search goes when only the time is limited
+const MAX_DEPTH: u32 = 64;
+
+/// searches every position of an EPD test suite and counts the `bm` moves found
+/// and the `am` moves avoided
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut path = None;
+    let mut movetime = None;
+    let mut depth = None;
+
+    let number = |value: Option<String>, flag: &str| {
+        value
+            .and_then(|v| v.parse::<u32>().ok())
+            .ok_or(format!("{} needs a number", flag))
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--movetime" => movetime = Some(number(args.next(), "--movetime")?),
+            "--depth" => depth = Some(number(args.next(), "--depth")?),
+            _ if arg.starts_with("--") => return Err(format!("unknown argument '{}'", arg)),
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    // without any limit every position gets a second
+    if depth.is_none() && movetime.is_none() {
+        movetime = Some(1000);
+    }
+
+    let path = path.ok_or(USAGE)?;
+    let text = fs::read_to_string(&path).map_err(|_| format!("unable to read '{}'", path))?;
+    let engine = Engine::new(depth.unwrap_or(MAX_DEPTH));
+    let (found, total) = solve(&text, &engine, movetime)?;
+
+    println!("solved {} of {} positions", found, total);
+    Ok(())
+}
+
+/// searches the positions of a suite, prints how each went and returns how many of them were solved
+fn solve(text: &str, engine: &Engine, movetime: Option<u32>) -> Result<(u32, u32), String> {
+    let never = AtomicBool::new(false);
+    let mut found = 0;
+    let mut total = 0;
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |e| format!("line {}: {}", i + 1, e);
+        let epd: Epd = line.parse().map_err(error)?;
+        let best = epd.moves("bm").map_err(error)?;
+        let avoid = epd.moves("am").map_err(error)?;
+        if best.is_empty() && avoid.is_empty() {
+            return Err(error("the position has no bm or am operation"));
+        }
+
+        let info = match movetime {
+            Some(ms) => {
+                let time = Duration::from_millis(ms as u64);
+                engine.analyze_for(&epd.game, engine.depth(), time, |_| ())
+            }
+            None => engine.analyze(&epd.game, engine.depth(), &never, |_| ()),
+        };
+        let Some(m) = info.as_ref().and_then(|info| info.best_move()) else {
+            return Err(error("the game is already over"));
+        };
+
+        let solved = (best.is_empty() || best.contains(&m)) && !avoid.contains(&m);
+        total += 1;
+        if solved {
+            found += 1;
+        }
+
+        let id = epd.id().map_or(format!("line {}", i + 1), str::to_string);
+        let mut expected = Vec::new();
+        if let Some(bm) = epd.operation("bm") {
+            expected.push(format!("bm {}", bm));
+        }
+        if let Some(am) = epd.operation("am") {
+            expected.push(format!("am {}", am));
+        }
+        let verdict = match solved {
+            true => "found ".green(),
+            false => "missed".red(),
+        };
+        println!(
+            "{} {}: played {}, {}, depth {}",
+            verdict,
+            id,
+            epd.game.san(&m),
+            expected.join(", "),
+            info.map_or(0, |info| info.depth)
+        );
+    }
+
+    Ok((found, total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mates_in_one() {
+        let suite = "\
+# back rank mates
+6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#; id \"back rank\";
+6k1/5ppp/8/8/8/8/8/R5K1 w - - am Ra7; id \"avoid\";
+
+r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - bm Qxf7#;
+";
+        let engine = Engine::new(2);
+        assert_eq!(solve(suite, &engine, None), Ok((3, 3)));
+        assert_eq!(solve(suite, &engine, Some(200)), Ok((3, 3)));
+
+        let missed = "6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra2;";
+        assert_eq!(solve(missed, &engine, None), Ok((0, 1)));
+    }
+
+    #[test]
+    fn rejects_broken_suites() {
+        let engine = Engine::new(1);
+        let no_operation = "6k1/5ppp/8/8/8/8/8/R5K1 w - - id \"nothing to find\";";
+        assert_eq!(
+            solve(no_operation, &engine, None),
+            Err("line 1: the position has no bm or am operation".to_string())
+        );
+        let illegal = "6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Rb8;";
+        assert!(solve(illegal, &engine, None)
+            .unwrap_err()
+            .starts_with("line 1: "));
+        let over = "R5k1/5ppp/8/8/8/8/8/6K1 b - - bm Kh8;";
+        assert!(solve(over, &engine, None).is_err());
+    }
+}
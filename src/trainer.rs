@@ -0,0 +1,233 @@
+use std::fs;
+
+use chess::{Orientation, Puzzle, RenderOptions, Theme};
+use colored::Colorize;
+use rand::seq::SliceRandom;
+
+use crate::cli::read_line;
+
+const USAGE: &str = "usage: puzzles FILE.epd|FILE.csv [--shuffle] [--ascii]";
+
+/// reads the puzzles of an EPD file or a CSV file in the Lichess format
+fn read_puzzles(path: &str) -> Result<Vec<Puzzle>, String> {
+    let text = fs::read_to_string(path).map_err(|_| format!("unable to read '{}'", path))?;
+    let csv = path.ends_with(".csv");
+
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#') && !line.starts_with("PuzzleId")
+        })
+        .map(|(i, line)| {
+            let puzzle = match csv {
+                true => Puzzle::from_csv(line),
+                false => Puzzle::from_epd(line),
+            };
+            puzzle.map_err(|e| format!("line {}: {}", i + 1, e))
+        })
+        .collect()
+}
+
+/// solved and tried puzzles, and how many were solved in a row
+#[derive(Debug, Default)]
+struct Score {
+    solved: u32,
+    tried: u32,
+    streak: u32,
+    best_streak: u32,
+}
+
+impl Score {
+    fn add(&mut self, solved: bool) {
+        self.tried += 1;
+        if solved {
+            self.solved += 1;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+        } else {
+            self.streak = 0;
+        }
+    }
+}
+
+enum Outcome {
+    Solved,
+    Failed,
+    Quit,
+}
+
+/// lets the user find the moves of the puzzle, playing the replies of the solution,
+/// `read` gives the user's input line by line
+fn solve(
+    puzzle: &Puzzle,
+    options: &RenderOptions,
+    mut read: impl FnMut() -> Option<String>,
+) -> Outcome {
+    let mut game = puzzle.game.clone();
+    let mut step = 0;
+    println!("{} to move, find the best move", game.side_to_move());
+
+    while step < puzzle.solution.len() {
+        println!("{}", game.render(options));
+        let Some(input) = read() else {
+            return Outcome::Quit;
+        };
+        let input = input.trim();
+
+        let expected = game.san(&puzzle.solution[step]);
+        match input {
+            "quit" => return Outcome::Quit,
+            "skip" => {
+                println!("the move was {}", expected);
+                return Outcome::Failed;
+            }
+            "" => continue,
+            _ => (),
+        }
+
+        let mut after = game.clone();
+        let m = input
+            .parse()
+            .or_else(|e| game.parse_san(input).or(Err(e)))
+            .and_then(|m| after.apply_move(m));
+        if let Err(e) = m {
+            println!("{}", format!("err: {}", e).red());
+            continue;
+        }
+        if !puzzle.accepts(step, &after) {
+            println!("{}", format!("wrong, the move was {}", expected).red());
+            return Outcome::Failed;
+        }
+        if after.is_over() {
+            break;
+        }
+        game = after;
+        step += 1;
+
+        if let Some(reply) = puzzle.solution.get(step) {
+            println!("{} {}", "correct, the reply is".green(), game.san(reply));
+            game.apply_move(*reply)
+                .expect("the solution was played through when reading it");
+            step += 1;
+        }
+    }
+
+    Outcome::Solved
+}
+
+/// goes through the puzzles of a file one by one, keeping score
+pub fn run(args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut path = None;
+    let mut shuffle = false;
+    let mut options = RenderOptions {
+        orientation: Orientation::SideToMove,
+        ..Default::default()
+    };
+
+    for arg in args {
+        match arg.as_str() {
+            "--shuffle" => shuffle = true,
+            "--ascii" => options.theme = Theme::ascii(),
+            _ if arg.starts_with("--") => return Err(format!("unknown argument '{}'", arg)),
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let mut puzzles = read_puzzles(&path.ok_or(USAGE)?)?;
+    if shuffle {
+        puzzles.shuffle(&mut rand::thread_rng());
+    }
+    println!("type your moves as 'e2e4' or in SAN, 'skip' shows the solution, 'quit' stops");
+
+    let mut score = Score::default();
+    for (i, puzzle) in puzzles.iter().enumerate() {
+        println!("{}", "----------------------------".cyan());
+        let mut title = format!("puzzle {}/{}", i + 1, puzzles.len());
+        if let Some(id) = &puzzle.id {
+            title.push_str(&format!(", {}", id));
+        }
+        if let Some(rating) = puzzle.rating {
+            title.push_str(&format!(", rated {}", rating));
+        }
+        println!("{}", title);
+
+        match solve(puzzle, &options, read_line) {
+            Outcome::Solved => {
+                println!("{}", "solved!".green());
+                score.add(true);
+            }
+            Outcome::Failed => score.add(false),
+            Outcome::Quit => break,
+        }
+        println!(
+            "score {}/{}, streak {}, best streak {}",
+            score.solved, score.tried, score.streak, score.best_streak
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solve_with(puzzle: &Puzzle, inputs: &[&str]) -> Outcome {
+        let mut inputs = inputs.iter().map(|i| i.to_string());
+        solve(puzzle, &RenderOptions::default(), || inputs.next())
+    }
+
+    fn ladder() -> Puzzle {
+        Puzzle::from_epd("7k/8/8/8/8/8/R7/1R4K1 w - - pv Rb7 Kg8 Ra8#;").unwrap()
+    }
+
+    #[test]
+    fn plays_the_replies() {
+        // Kg8 is played for the opponent between the two moves
+        assert!(matches!(
+            solve_with(&ladder(), &["Rb7", "a2a8"]),
+            Outcome::Solved
+        ));
+        assert!(matches!(
+            solve_with(&ladder(), &["", "b1b7", "oops", "Ra8#"]),
+            Outcome::Solved
+        ));
+    }
+
+    #[test]
+    fn wrong_moves_fail() {
+        assert!(matches!(solve_with(&ladder(), &["Kf2"]), Outcome::Failed));
+        assert!(matches!(
+            solve_with(&ladder(), &["Rb7", "Rb8+"]),
+            Outcome::Failed
+        ));
+        assert!(matches!(solve_with(&ladder(), &["skip"]), Outcome::Failed));
+        assert!(matches!(
+            solve_with(&ladder(), &["Rb7", "quit"]),
+            Outcome::Quit
+        ));
+        assert!(matches!(solve_with(&ladder(), &["Rb7"]), Outcome::Quit));
+    }
+
+    #[test]
+    fn other_mates_solve() {
+        let puzzle = Puzzle::from_epd("6k1/5ppp/8/8/8/8/8/R3R1K1 w - - bm Ra8#;").unwrap();
+        assert!(matches!(solve_with(&puzzle, &["Re8#"]), Outcome::Solved));
+    }
+
+    #[test]
+    fn score_and_streak() {
+        let mut score = Score::default();
+        for solved in [true, true, false, true] {
+            score.add(solved);
+        }
+        assert_eq!(
+            (score.solved, score.tried, score.streak, score.best_streak),
+            (3, 4, 1, 2)
+        );
+        score.add(false);
+        assert_eq!((score.streak, score.best_streak), (0, 2));
+    }
+}
@@ -0,0 +1,389 @@
+use std::{
+    io::{stdout, Stdout, Write},
+    time::Duration,
+};
+
+use chess::{
+    format_duration, BoardView, Book, Clock, FigureColor, FigureVariant, Game, GameState, Move,
+    Orientation, RenderOptions, Square,
+};
+use colored::Colorize;
+use crossterm::{
+    cursor,
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
+    execute, queue,
+    style::Print,
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use crate::cli::{Bot, Hints};
+
+const PANEL_X: u16 = BoardView::WIDTH + 4;
+const HELP: &str = "arrows/mouse: pick  enter: select/move  esc: cancel  ?: hint  f: flip  q: quit";
+const PROMOTION_HELP: &str = "promote to q: queen  r: rook  b: bishop  n: knight  esc: cancel";
+
+struct Tui<'a> {
+    game: &'a mut Game,
+    options: RenderOptions,
+    clock: Clock,
+    book: Option<&'a Book>,
+    bot: Option<&'a Bot>,
+    hints: &'a mut Hints,
+    cursor: Square,
+    /// a pawn move waiting for the figure it becomes
+    promotion: Option<(Square, Square)>,
+    message: Option<String>,
+    quit: bool,
+}
+
+impl Tui<'_> {
+    fn view(&self) -> BoardView<'_> {
+        self.game.render(&self.options)
+    }
+
+    fn is_over(&self) -> bool {
+        self.game.is_over()
+    }
+
+    fn status(&self) -> String {
+        match self.game.state() {
+            GameState::Check(_) => format!("{}", "check!".yellow()),
+            GameState::Checkmate(color) => format!("checkmate, {} wins", color.opposite()),
+            GameState::Stalemate(_) => "stalemate, it's a draw".to_string(),
+            GameState::OutOfTime(color) => {
+                format!("{} ran out of time, {} wins", color, color.opposite())
+            }
+            GameState::Play => String::new(),
+        }
+    }
+
+    /// the book moves of the current position with their share of the weights
+    fn book_rows(&self) -> Vec<String> {
+        let Some(book) = self.book else {
+            return Vec::new();
+        };
+        let moves = book.moves(self.game);
+        let total: u32 = moves.iter().map(|(_, weight)| *weight as u32).sum();
+
+        let mut rows = vec![String::new(), "book".bold().to_string()];
+        if moves.is_empty() {
+            rows.push("out of book".dimmed().to_string());
+        }
+        for (m, weight) in moves.iter().take(5) {
+            let share = *weight as u32 * 100 / total.max(1);
+            rows.push(format!("{:<8}{:>3}%", self.game.san(m), share));
+        }
+        rows
+    }
+
+    fn draw(&self, out: &mut Stdout) -> std::io::Result<()> {
+        let (_, height) = terminal::size()?;
+        let view = self.view();
+        let board = view.to_string();
+
+        let mut lines: Vec<(u16, u16, String)> = Vec::new();
+        for (y, line) in board.lines().enumerate() {
+            lines.push((0, y as u16, line.to_string()));
+        }
+
+        let mut panel = vec![
+            format!("{}'s turn", self.game.side_to_move()),
+            self.status(),
+            String::new(),
+        ];
+        for color in [FigureColor::White, FigureColor::Black] {
+            let marker = if self.clock.running() == Some(color) {
+                "◀"
+            } else {
+                " "
+            };
+            panel.push(format!(
+                "{} {} {}",
+                color,
+                format_duration(self.clock.shown(color)),
+                marker
+            ));
+        }
+        panel.push(String::new());
+        panel.extend(view.material());
+        panel.extend(self.book_rows());
+        panel.push(String::new());
+        panel.push("moves".bold().to_string());
+
+        let room = (height as usize).saturating_sub(panel.len() + 3).max(1);
+        let rows = view.move_rows();
+        panel.extend(rows.iter().skip(rows.len().saturating_sub(room)).cloned());
+
+        for (y, line) in panel.into_iter().enumerate() {
+            lines.push((PANEL_X, y as u16, line));
+        }
+
+        let bottom = BoardView::HEIGHT + 1;
+        lines.push((0, bottom, self.message.clone().unwrap_or_default()));
+        let help = match self.promotion {
+            Some(_) => PROMOTION_HELP,
+            None => HELP,
+        };
+        lines.push((0, bottom + 1, help.dimmed().to_string()));
+
+        // drawn over the last frame row by row, clearing only what the new one leaves out
+        lines.sort_by_key(|(x, y, _)| (*y, *x));
+        let mut row = 0;
+        for (x, y, line) in lines {
+            for empty in row..y {
+                queue!(
+                    out,
+                    cursor::MoveTo(0, empty),
+                    Clear(ClearType::UntilNewLine)
+                )?;
+            }
+            queue!(
+                out,
+                cursor::MoveTo(x, y),
+                Print(line),
+                Clear(ClearType::UntilNewLine)
+            )?;
+            row = y + 1;
+        }
+        queue!(
+            out,
+            cursor::MoveTo(0, row),
+            Clear(ClearType::FromCursorDown)
+        )?;
+        out.flush()
+    }
+
+    fn move_cursor(&mut self, right: i8, up: i8) {
+        let (right, up) = if self.view().white_at_bottom() {
+            (right, up)
+        } else {
+            (-right, -up)
+        };
+        if let Some(next) = self.cursor.offset(right, up) {
+            self.cursor = next;
+        }
+        self.options.cursor = Some(self.cursor);
+    }
+
+    /// selects the figure on `square`, or moves the selected one there
+    fn activate(&mut self, square: Square) {
+        self.message = None;
+
+        if let Some(from) = self.options.selected {
+            let targets: Vec<Move> = self
+                .game
+                .legal_moves()
+                .into_iter()
+                .filter(|m| m.from == from && m.to == square)
+                .collect();
+
+            if targets.iter().any(|m| m.promotion.is_some()) {
+                self.promotion = Some((from, square));
+                return;
+            }
+            if !targets.is_empty() {
+                self.options.selected = None;
+                self.play(Move::new(from, square));
+                return;
+            }
+        }
+
+        match self.game.board.get(&square) {
+            Some(figure) if figure.color == self.game.side_to_move() => {
+                self.options.selected = Some(square)
+            }
+            Some(_) if self.options.selected.is_none() => {
+                self.message = Some("err: cannot move opponent's figure".red().to_string())
+            }
+            _ => self.options.selected = None,
+        }
+    }
+
+    fn play(&mut self, m: Move) {
+        match self.game.apply_move(m) {
+            Ok(_) => self.moved(),
+            Err(e) => self.message = Some(format!("err: {}", e).red().to_string()),
+        }
+    }
+
+    /// finishes the waiting pawn move with the figure picked by its letter
+    fn promote(&mut self, variant: FigureVariant) {
+        if let Some((from, to)) = self.promotion.take() {
+            self.options.selected = None;
+            self.play(Move::with_promotion(from, to, variant));
+        }
+    }
+
+    /// marks the figure the engine would move, or the whole move when asked again
+    fn hint(&mut self, out: &mut Stdout) -> std::io::Result<()> {
+        self.message = Some("thinking...".dimmed().to_string());
+        self.draw(out)?;
+        self.message = Some(match self.hints.give(self.game, &mut self.options) {
+            Ok(text) => text,
+            Err(e) => format!("err: {}", e).red().to_string(),
+        });
+        if let Some(from) = self.options.selected {
+            self.cursor = from;
+            self.options.cursor = Some(from);
+        }
+        Ok(())
+    }
+
+    /// hands the turn over on the clock after a move
+    fn moved(&mut self) {
+        self.options.hint = None;
+        self.clock.press();
+        if self.game.is_over() {
+            self.clock.stop();
+        }
+    }
+
+    /// lets the bot move when it is its turn, the board shows it thinking meanwhile
+    fn bot_turn(&mut self, out: &mut Stdout) -> std::io::Result<()> {
+        let Some(bot) = self.bot else {
+            return Ok(());
+        };
+        if self.is_over() || self.game.side_to_move() != bot.color {
+            return Ok(());
+        }
+        self.message = Some("thinking...".dimmed().to_string());
+        self.draw(out)?;
+        self.message = None;
+        if let Some(san) = bot.play(self.game) {
+            self.message = Some(format!("{} plays {}", bot.color.name(), san));
+            self.moved();
+        }
+        Ok(())
+    }
+
+    fn flip(&mut self) {
+        self.options.orientation = if self.view().white_at_bottom() {
+            Orientation::Black
+        } else {
+            Orientation::White
+        };
+    }
+
+    fn on_key(&mut self, key: KeyEvent, out: &mut Stdout) -> std::io::Result<()> {
+        if key.kind != KeyEventKind::Press {
+            return Ok(());
+        }
+
+        let ctrl_c =
+            key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+        if self.promotion.is_some() && !ctrl_c {
+            match key.code {
+                KeyCode::Char(c @ ('q' | 'r' | 'b' | 'n')) => {
+                    if let Some(variant) = FigureVariant::from_char(c) {
+                        self.promote(variant);
+                    }
+                }
+                KeyCode::Esc => self.promotion = None,
+                _ => (),
+            }
+            return Ok(());
+        }
+
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            _ if ctrl_c => self.quit = true,
+            KeyCode::Char('f') => self.flip(),
+            KeyCode::Esc => self.options.selected = None,
+            _ if self.is_over() => (),
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(0, 1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(0, -1),
+            KeyCode::Left | KeyCode::Char('h') => self.move_cursor(-1, 0),
+            KeyCode::Right | KeyCode::Char('l') => self.move_cursor(1, 0),
+            KeyCode::Enter | KeyCode::Char(' ') => self.activate(self.cursor),
+            KeyCode::Char('?') => self.hint(out)?,
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn on_mouse(&mut self, mouse: MouseEvent) {
+        if self.is_over() || self.promotion.is_some() {
+            return;
+        }
+        if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
+            if let Some(square) = self.view().square_at(mouse.column, mouse.row) {
+                self.cursor = square;
+                self.options.cursor = Some(square);
+                self.activate(square);
+            }
+        }
+    }
+
+    fn event_loop(&mut self, out: &mut Stdout) -> std::io::Result<()> {
+        self.clock.start(self.game.side_to_move());
+
+        while !self.quit {
+            if let Some(color) = self.clock.flagged() {
+                self.game.lose_on_time(color);
+                self.clock.stop();
+            }
+            self.bot_turn(out)?;
+            self.draw(out)?;
+
+            if !event::poll(Duration::from_millis(200))? {
+                continue;
+            }
+            match event::read()? {
+                Event::Key(key) => self.on_key(key, out)?,
+                Event::Mouse(mouse) => self.on_mouse(mouse),
+                Event::Resize(..) => queue!(out, Clear(ClearType::All))?,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// plays the game in a full-screen terminal ui until it ends and the user quits
+pub fn run(
+    game: &mut Game,
+    options: &RenderOptions,
+    clock: Clock,
+    book: Option<&Book>,
+    bot: Option<&Bot>,
+    hints: &mut Hints,
+) -> std::io::Result<()> {
+    // the cursor starts among the figures of the user
+    let me = bot.map_or(game.side_to_move(), |bot| bot.color.opposite());
+    let cursor: Square = match me {
+        FigureColor::White => "e2",
+        FigureColor::Black => "e7",
+    }
+    .parse()
+    .expect("the cursor starts on a valid field");
+    let mut tui = Tui {
+        game,
+        options: RenderOptions {
+            cursor: Some(cursor),
+            // the ui draws its own panel with the clocks, using the rows of the board's one
+            panel: false,
+            ..*options
+        },
+        clock,
+        book,
+        bot,
+        hints,
+        cursor,
+        promotion: None,
+        message: None,
+        quit: false,
+    };
+
+    let mut out = stdout();
+    terminal::enable_raw_mode()?;
+    execute!(out, EnterAlternateScreen, EnableMouseCapture, cursor::Hide)?;
+
+    let result = tui.event_loop(&mut out);
+
+    execute!(out, DisableMouseCapture, LeaveAlternateScreen, cursor::Show)?;
+    terminal::disable_raw_mode()?;
+    result
+}
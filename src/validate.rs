@@ -0,0 +1,344 @@
+use std::fmt::Display;
+
+use crate::{
+    figure::{Figure, FigureColor, FigureVariant},
+    game::Game,
+    square::{Rank, Square},
+};
+
+/// something that makes a position impossible to reach in a real game, see [`Game::validate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationError {
+    MissingKing(FigureColor),
+    ExtraKings(FigureColor),
+    TooManyPawns(FigureColor),
+    TooManyFigures(FigureColor),
+    /// more queens, rooks, bishops or knights than the missing pawns could have become
+    TooManyPromotedFigures(FigureColor),
+    PawnOnBackRank(Square),
+    /// the side that is not to move stands in check
+    OpponentInCheck(FigureColor),
+    CastlingRights(FigureColor),
+    EnPassant(Square),
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingKing(color) => write!(f, "{} has no king", color.name()),
+            Self::ExtraKings(color) => write!(f, "{} has more than one king", color.name()),
+            Self::TooManyPawns(color) => write!(f, "{} has more than 8 pawns", color.name()),
+            Self::TooManyFigures(color) => write!(f, "{} has more than 16 figures", color.name()),
+            Self::TooManyPromotedFigures(color) => write!(
+                f,
+                "{} has more promoted figures than it has missing pawns",
+                color.name()
+            ),
+            Self::PawnOnBackRank(field) => {
+                write!(f, "the pawn on {} stands on the first or last rank", field)
+            }
+            Self::OpponentInCheck(color) => {
+                write!(f, "{} is in check but it's not its turn", color.name())
+            }
+            Self::CastlingRights(color) => write!(
+                f,
+                "the castling rights of {} do not match its king and rooks",
+                color.name()
+            ),
+            Self::EnPassant(field) => write!(
+                f,
+                "en passant on {} does not match a pawn that just moved two fields",
+                field
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl Game {
+    /// reports everything that makes the position impossible to reach in a real game,
+    /// FEN and the setup editor happily produce such positions
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        for color in [FigureColor::White, FigureColor::Black] {
+            let count = |variant| {
+                self.board
+                    .values()
+                    .filter(|f| **f == Figure::new(color, variant))
+                    .count()
+            };
+
+            match count(FigureVariant::King) {
+                0 => errors.push(ValidationError::MissingKing(color)),
+                1 => (),
+                _ => errors.push(ValidationError::ExtraKings(color)),
+            }
+
+            let pawns = count(FigureVariant::Pawn);
+            if pawns > 8 {
+                errors.push(ValidationError::TooManyPawns(color));
+            }
+
+            if self.board.values().filter(|f| f.color == color).count() > 16 {
+                errors.push(ValidationError::TooManyFigures(color));
+            }
+
+            let promoted = count(FigureVariant::Queen).saturating_sub(1)
+                + count(FigureVariant::Rook).saturating_sub(2)
+                + count(FigureVariant::Bishop).saturating_sub(2)
+                + count(FigureVariant::Knight).saturating_sub(2);
+            if pawns <= 8 && promoted > 8 - pawns {
+                errors.push(ValidationError::TooManyPromotedFigures(color));
+            }
+
+            if !self.castling_rights_valid(color) {
+                errors.push(ValidationError::CastlingRights(color));
+            }
+        }
+
+        let mut back_rank_pawns: Vec<Square> = self
+            .board
+            .iter()
+            .filter(|(c, f)| {
+                f.variant == FigureVariant::Pawn && matches!(c.rank(), Rank::FIRST | Rank::EIGHTH)
+            })
+            .map(|(c, _)| *c)
+            .collect();
+        back_rank_pawns.sort();
+        errors.extend(
+            back_rank_pawns
+                .into_iter()
+                .map(ValidationError::PawnOnBackRank),
+        );
+
+        let waiting = self.side_to_move().opposite();
+        if self.is_in_check(waiting) {
+            errors.push(ValidationError::OpponentInCheck(waiting));
+        }
+
+        if let Some(field) = self.en_passant() {
+            if !self.en_passant_valid(field) {
+                errors.push(ValidationError::EnPassant(field));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// the game when [`Game::validate`] finds nothing wrong, for readers whose errors are plain messages
+    pub(crate) fn validated(self) -> Result<Self, &'static str> {
+        match self.validate() {
+            Ok(()) => Ok(self),
+            Err(_) => Err("the position cannot be played"),
+        }
+    }
+
+    fn castling_rights_valid(&self, color: FigureColor) -> bool {
+        let rank = color.home_rank();
+        let king = self.king_position(color).filter(|k| k.rank() == rank);
+
+        [true, false].iter().all(|kingside| {
+            let Some(file) = self.castling_rights().get(color, *kingside) else {
+                return true;
+            };
+            let rook = self.board.get(&Square::new(file, rank))
+                == Some(&Figure::new(color, FigureVariant::Rook));
+            rook && king.is_some_and(|k| (file > k.file()) == *kingside)
+        })
+    }
+
+    /// the skipped field has to be empty, with the pawn that moved right in front of it
+    /// and the field it came from empty as well
+    fn en_passant_valid(&self, field: Square) -> bool {
+        let mover = self.side_to_move().opposite();
+        let dir = mover.pawn_direction();
+
+        if field.rank().offset(-dir) != Some(mover.pawn_rank()) {
+            return false;
+        }
+
+        let (Some(from), Some(to)) = (field.offset(0, -dir), field.offset(0, dir)) else {
+            return false;
+        };
+
+        !self.board.contains_key(&field)
+            && !self.board.contains_key(&from)
+            && self.board.get(&to) == Some(&Figure::new(mover, FigureVariant::Pawn))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(fen: &str) -> Vec<ValidationError> {
+        Game::from_fen(fen)
+            .unwrap()
+            .validate()
+            .err()
+            .unwrap_or_default()
+    }
+
+    fn square(s: &str) -> Square {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn playable_positions() {
+        assert_eq!(Game::new().validate(), Ok(()));
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+            "8/8/4k3/8/8/3K4/8/8 w - - 0 1",
+            // a promoted queen for a missing pawn
+            "4k3/8/8/8/8/8/PPPPPPP1/QQ2K3 w - - 0 1",
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1",
+        ] {
+            assert!(errors(fen).is_empty(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn kings() {
+        use ValidationError::*;
+        assert_eq!(
+            errors("8/8/8/8/8/8/8/4K3 w - - 0 1"),
+            [MissingKing(FigureColor::Black)]
+        );
+        assert_eq!(
+            errors("4k3/8/8/8/8/8/8/8 w - - 0 1"),
+            [MissingKing(FigureColor::White)]
+        );
+        assert_eq!(
+            errors("4k3/8/8/8/8/8/8/K3K3 w - - 0 1"),
+            [ExtraKings(FigureColor::White)]
+        );
+    }
+
+    #[test]
+    fn too_many_figures() {
+        use ValidationError::*;
+        assert_eq!(
+            errors("4k3/p7/8/8/8/P7/PPPPPPPP/4K3 w - - 0 1"),
+            [TooManyPawns(FigureColor::White)]
+        );
+        // a full army and a pawn on top
+        assert_eq!(
+            errors("rnbqkbnr/pppppppp/8/8/8/P7/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            [
+                TooManyPawns(FigureColor::White),
+                TooManyFigures(FigureColor::White)
+            ]
+        );
+        // two queens while every pawn is still there
+        assert_eq!(
+            errors(
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"
+                    .replace("8/8/8/8", "8/8/8/3q4")
+                    .as_str()
+            ),
+            [
+                TooManyFigures(FigureColor::Black),
+                TooManyPromotedFigures(FigureColor::Black)
+            ]
+        );
+        assert_eq!(
+            errors("4k3/8/8/8/8/8/PPPPPPP1/QQQ1K3 w - - 0 1"),
+            [TooManyPromotedFigures(FigureColor::White)]
+        );
+    }
+
+    #[test]
+    fn pawns_on_back_ranks() {
+        assert_eq!(
+            errors("P3k3/8/8/8/8/8/8/4K2p w - - 0 1"),
+            [
+                ValidationError::PawnOnBackRank(square("h1")),
+                ValidationError::PawnOnBackRank(square("a8"))
+            ]
+        );
+    }
+
+    #[test]
+    fn side_not_to_move_in_check() {
+        assert_eq!(
+            errors("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1"),
+            [ValidationError::OpponentInCheck(FigureColor::Black)]
+        );
+        assert!(errors("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1").is_empty());
+    }
+
+    #[test]
+    fn castling_rights() {
+        use ValidationError::CastlingRights;
+        // FEN refuses such rights, but the board can be changed after reading it
+        let mut game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        game.board.remove(&square("h8"));
+        assert_eq!(
+            game.validate(),
+            Err(vec![CastlingRights(FigureColor::Black)])
+        );
+
+        let mut game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let king = game.board.remove(&square("e1")).unwrap();
+        game.board.insert(square("e2"), king);
+        assert_eq!(
+            game.validate(),
+            Err(vec![CastlingRights(FigureColor::White)])
+        );
+    }
+
+    #[test]
+    fn en_passant() {
+        use ValidationError::EnPassant;
+        // no pawn in front of the field
+        assert_eq!(
+            errors("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq d6 0 1"),
+            [EnPassant(square("d6"))]
+        );
+        // white's pawn can't have just moved when white is to move
+        assert_eq!(
+            errors("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e3 0 1"),
+            [EnPassant(square("e3"))]
+        );
+        assert_eq!(
+            errors("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e4 0 1"),
+            [EnPassant(square("e4"))]
+        );
+        assert!(errors("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").is_empty());
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let errors = errors("8/8/8/8/8/8/8/4K2p b - e6 0 1");
+        assert_eq!(
+            errors,
+            [
+                ValidationError::MissingKing(FigureColor::Black),
+                ValidationError::PawnOnBackRank(square("h1")),
+                ValidationError::EnPassant(square("e6")),
+            ]
+        );
+        assert_eq!(errors[0].to_string(), "black has no king");
+    }
+
+    #[test]
+    fn readers_reject_impossible_positions() {
+        let fen = "8/8/8/8/8/8/8/4K3 w - - 0 1";
+        assert!(Game::from_fen(fen).is_ok());
+        let pgn = format!("[FEN \"{}\"]\n[SetUp \"1\"]\n\n*", fen);
+        assert_eq!(
+            Game::from_pgn(&pgn).err(),
+            Some("the position cannot be played")
+        );
+        assert!("8/8/8/8/8/8/8/4K3 w - - bm Kd2;"
+            .parse::<crate::Epd>()
+            .is_err());
+    }
+}
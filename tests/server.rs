@@ -0,0 +1,294 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpStream,
+    process::{Child, Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use serde_json::{json, Value};
+
+/// a `chess-server` on a port of its own, stopped when dropped
+struct Server {
+    process: Child,
+    port: u16,
+}
+
+impl Server {
+    fn start() -> Self {
+        let mut process = Command::new(env!("CARGO_BIN_EXE_chess-server"))
+            .args(["--port", "0"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("the server starts");
+        let mut line = String::new();
+        let stdout = process.stdout.take().expect("stdout is piped");
+        BufReader::new(stdout)
+            .read_line(&mut line)
+            .expect("the server prints its port");
+        let port = line
+            .trim()
+            .rsplit(' ')
+            .next()
+            .and_then(|port| port.parse().ok())
+            .expect("the server prints its port");
+        Server { process, port }
+    }
+
+    fn connect(&self) -> Client {
+        let stream = TcpStream::connect(("127.0.0.1", self.port)).expect("the server accepts");
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        Client {
+            reader: BufReader::new(stream.try_clone().unwrap()),
+            stream,
+        }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+struct Client {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+}
+
+impl Client {
+    fn send(&mut self, request: Value) {
+        writeln!(self.stream, "{}", request).unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut line = String::new();
+        self.reader
+            .read_line(&mut line)
+            .expect("the server answers");
+        serde_json::from_str(&line).expect("the server sends JSON")
+    }
+
+    /// the next event, which has to be of the given type
+    fn expect(&mut self, kind: &str) -> Value {
+        let event = self.receive();
+        assert_eq!(event["type"], kind, "{}", event);
+        event
+    }
+}
+
+/// two clients playing game 1, white first
+fn pair(server: &Server, time_control: Option<&str>) -> (Client, Client) {
+    let mut white = server.connect();
+    let mut black = server.connect();
+    white.send(json!({ "type": "hello", "name": "alice" }));
+    white.expect("welcome");
+    black.send(json!({ "type": "hello", "name": "bob" }));
+    black.expect("welcome");
+
+    white.send(json!({ "type": "seek", "time_control": time_control }));
+    white.expect("seeking");
+    black.send(json!({ "type": "seek", "time_control": time_control }));
+    for (client, color) in [(&mut white, "White"), (&mut black, "Black")] {
+        let started = client.expect("started");
+        assert_eq!(started["game"], 1);
+        assert_eq!(started["color"], color);
+        assert_eq!(
+            (&started["white"], &started["black"]),
+            (&json!("alice"), &json!("bob"))
+        );
+        client.expect("update");
+    }
+    (white, black)
+}
+
+#[test]
+fn hello() {
+    let server = Server::start();
+    let mut client = server.connect();
+    client.send(json!({ "type": "hello", "name": "carol" }));
+    assert_eq!(client.expect("welcome")["name"], "carol");
+    client.send(json!({ "type": "dance" }));
+    client.expect("error");
+}
+
+#[test]
+fn seek_and_pairing() {
+    let server = Server::start();
+    let (mut white, _black) = pair(&server, Some("5+3"));
+    white.send(json!({ "type": "list" }));
+    let games = white.expect("games");
+    assert_eq!(games["games"][0]["white"], "alice");
+    assert_eq!(games["games"][0]["moves"], 0);
+}
+
+#[test]
+fn different_time_controls_do_not_pair() {
+    let server = Server::start();
+    let mut first = server.connect();
+    let mut second = server.connect();
+    first.send(json!({ "type": "seek", "time_control": "5" }));
+    first.expect("seeking");
+    second.send(json!({ "type": "seek", "time_control": "3+2" }));
+    second.expect("seeking");
+}
+
+#[test]
+fn seeking_again_replaces_the_seek() {
+    let server = Server::start();
+    let mut first = server.connect();
+    let mut second = server.connect();
+    first.send(json!({ "type": "seek", "time_control": "5" }));
+    first.expect("seeking");
+    first.send(json!({ "type": "seek", "time_control": "3+2" }));
+    first.expect("seeking");
+
+    // the first seek is gone, so a third player seeking it waits
+    let mut third = server.connect();
+    third.send(json!({ "type": "seek", "time_control": "5" }));
+    third.expect("seeking");
+    second.send(json!({ "type": "seek", "time_control": "3+2" }));
+    second.expect("started");
+    assert_eq!(first.expect("started")["color"], "White");
+}
+
+#[test]
+fn overlong_lines_cut_the_client_off() {
+    let server = Server::start();
+    let mut client = server.connect();
+    let name = "x".repeat(10_000);
+    client.send(json!({ "type": "hello", "name": name }));
+    assert_eq!(client.expect("error")["message"], "the request is too long");
+    let mut rest = String::new();
+    assert_eq!(client.reader.read_line(&mut rest).unwrap_or(0), 0);
+}
+
+#[test]
+fn bad_time_controls_leave_the_server_running() {
+    let server = Server::start();
+    let mut client = server.connect();
+    for control in ["nan", "inf", "1e300", "-1", "5+x"] {
+        client.send(json!({ "type": "seek", "time_control": control }));
+        client.expect("error");
+    }
+    client.send(json!({ "type": "hello", "name": "dave" }));
+    client.expect("welcome");
+    let (mut white, _black) = pair(&server, None);
+    white.send(json!({ "type": "move", "game": 1, "move": "e2e4" }));
+    white.expect("update");
+}
+
+#[test]
+fn moves() {
+    let server = Server::start();
+    let (mut white, mut black) = pair(&server, None);
+
+    black.send(json!({ "type": "move", "game": 1, "move": "e7e5" }));
+    assert_eq!(black.expect("error")["message"], "it is not your turn");
+    white.send(json!({ "type": "move", "game": 1, "move": "e2e5" }));
+    white.expect("error");
+
+    white.send(json!({ "type": "move", "game": 1, "move": "e2e4" }));
+    for client in [&mut white, &mut black] {
+        let update = client.expect("update");
+        assert_eq!(update["position"]["moves"], json!(["e2e4"]));
+    }
+}
+
+#[test]
+fn flagged_players_cannot_move() {
+    let server = Server::start();
+    let (mut white, _black) = pair(&server, Some("0.001"));
+    thread::sleep(Duration::from_millis(300));
+    white.send(json!({ "type": "move", "game": 1, "move": "e2e4" }));
+    let over = white.expect("over");
+    assert_eq!(
+        (&over["result"], &over["reason"]),
+        (&json!("0-1"), &json!("time"))
+    );
+    // the clocks may have been checked first, leaving no table behind
+    let error = white.expect("error");
+    assert!(
+        ["the game is over", "there is no such game"].contains(&error["message"].as_str().unwrap()),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn watch() {
+    let server = Server::start();
+    let (mut white, _black) = pair(&server, None);
+    let mut spectator = server.connect();
+    spectator.send(json!({ "type": "watch", "game": 2 }));
+    spectator.expect("error");
+    for _ in 0..2 {
+        spectator.send(json!({ "type": "watch", "game": 1 }));
+        spectator.expect("update");
+    }
+
+    white.send(json!({ "type": "move", "game": 1, "move": "d2d4" }));
+    let update = spectator.expect("update");
+    assert_eq!(update["position"]["moves"], json!(["d2d4"]));
+    // watching twice still sends every update once
+    spectator.send(json!({ "type": "list" }));
+    spectator.expect("games");
+}
+
+#[test]
+fn resign() {
+    let server = Server::start();
+    let (mut white, mut black) = pair(&server, None);
+    let mut spectator = server.connect();
+    spectator.send(json!({ "type": "resign", "game": 1 }));
+    spectator.expect("error");
+
+    black.send(json!({ "type": "resign", "game": 1 }));
+    for client in [&mut white, &mut black] {
+        let over = client.expect("over");
+        assert_eq!(
+            (&over["result"], &over["reason"]),
+            (&json!("1-0"), &json!("resignation"))
+        );
+    }
+    // nobody watches the finished game, so it is gone
+    white.send(json!({ "type": "move", "game": 1, "move": "e2e4" }));
+    assert_eq!(white.expect("error")["message"], "there is no such game");
+    white.send(json!({ "type": "list" }));
+    assert_eq!(white.expect("games")["games"], json!([]));
+}
+
+#[test]
+fn finished_games_stay_while_watched() {
+    let server = Server::start();
+    let (mut white, mut black) = pair(&server, None);
+    let mut spectator = server.connect();
+    spectator.send(json!({ "type": "watch", "game": 1 }));
+    spectator.expect("update");
+
+    black.send(json!({ "type": "resign", "game": 1 }));
+    spectator.expect("over");
+    white.expect("over");
+    white.send(json!({ "type": "move", "game": 1, "move": "e2e4" }));
+    assert_eq!(white.expect("error")["message"], "the game is over");
+
+    drop(spectator);
+    thread::sleep(Duration::from_millis(200));
+    white.send(json!({ "type": "list" }));
+    assert_eq!(white.expect("games")["games"], json!([]));
+}
+
+#[test]
+fn disconnect() {
+    let server = Server::start();
+    let (white, mut black) = pair(&server, None);
+    drop(white);
+    let over = black.expect("over");
+    assert_eq!(
+        (&over["result"], &over["reason"]),
+        (&json!("0-1"), &json!("disconnected"))
+    );
+}